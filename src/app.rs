@@ -1,57 +1,709 @@
-use crate::config::{AppConfig, Config};
-use crate::os::{OsOperations, PartitionInfo};
-use sysinfo::Disks;
+use crate::config::{
+    AppConfig, Condition, Config, ConnectivityConfig, PartitionRequirements, PathRequirements,
+};
+use crate::os::{
+    CommandResult, DisplayInfo, FsSpace, LocalNetwork, MountedFs, NetworkState, OsOperations,
+    PartitionInfo, PowerState, SystemLoad, UsbDeviceInfo,
+};
+use crate::schedule;
+use chrono::{Local, NaiveDateTime};
+use std::cell::{Cell, OnceCell, Ref, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
 
+/// How long mount, path and file value checks wait for the filesystem to answer.
+const FS_RESPONSE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Result of evaluating one node of a condition tree. `children` line up with
+/// the node's sub-conditions, so the root's children match `Conditions::all`.
 pub struct ConditionStatus {
-    pub internet_ok: bool,
-    pub partition_ok: bool,
+    pub label: String,
+    pub ok: bool,
+    /// Waiting for a background check; `ok` is false until it arrives.
+    pub pending: bool,
+    pub children: Vec<ConditionStatus>,
+    /// Where a partition condition found its partition.
+    pub mount_point: Option<PathBuf>,
+    /// Extra explanation, e.g. a failed command's stderr.
+    pub detail: Option<String>,
 }
 
-pub fn check_app_conditions(
+impl ConditionStatus {
+    pub fn mark(&self) -> &'static str {
+        match (self.ok, self.pending) {
+            (true, _) => "✅",
+            (false, true) => "⏳",
+            (false, false) => "❌",
+        }
+    }
+
+    /// First mount point along the branches that made this node pass.
+    pub fn mount_point(&self) -> Option<&Path> {
+        if !self.ok {
+            return None;
+        }
+        self.mount_point
+            .as_deref()
+            .or_else(|| self.children.iter().find_map(|c| c.mount_point()))
+    }
+
+    fn write_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let mark = self.mark();
+        writeln!(f, "{:indent$}{mark} {}", "", self.label, indent = depth * 2)?;
+        for line in self.detail.iter().flat_map(|d| d.lines()) {
+            writeln!(f, "{:indent$}{line}", "", indent = depth * 2 + 3)?;
+        }
+        for child in &self.children {
+            child.write_tree(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ConditionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_tree(f, 0)
+    }
+}
+
+/// A check slow enough that the GUI hands it to the `CheckWorker`.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum SlowCheck {
+    Host {
+        host: String,
+        port: Option<u16>,
+        timeout_ms: u64,
+    },
+    Command {
+        command: String,
+        timeout_ms: u64,
+    },
+}
+
+enum SlowResult {
+    Host(bool),
+    Command(CommandResult),
+}
+
+/// Runs slow checks on its own thread so the GUI never waits for them.
+/// Results land in the cache as they arrive, and until the first one does the
+/// condition shows as pending.
+struct CheckWorker {
+    jobs: Sender<SlowCheck>,
+    results: Receiver<(SlowCheck, SlowResult)>,
+    /// Sent and not answered yet, so a hanging check isn't queued again.
+    running: RefCell<HashSet<SlowCheck>>,
+    /// Answered since the last `refresh`.
+    fresh: RefCell<HashSet<SlowCheck>>,
+}
+
+impl CheckWorker {
+    fn spawn(on_result: impl Fn() + Send + 'static) -> Self {
+        let (jobs, job_queue) = mpsc::channel::<SlowCheck>();
+        let (result_sender, results) = mpsc::channel();
+        thread::spawn(move || {
+            let os_ops = crate::os::get_os_operations();
+            for check in job_queue {
+                let result = match &check {
+                    SlowCheck::Host {
+                        host,
+                        port,
+                        timeout_ms,
+                    } => SlowResult::Host(os_ops.is_host_reachable(
+                        host,
+                        *port,
+                        Duration::from_millis(*timeout_ms),
+                    )),
+                    SlowCheck::Command {
+                        command,
+                        timeout_ms,
+                    } => SlowResult::Command(
+                        os_ops.run_check_command(command, Duration::from_millis(*timeout_ms)),
+                    ),
+                };
+                if result_sender.send((check, result)).is_err() {
+                    break;
+                }
+                on_result();
+            }
+        });
+        Self {
+            jobs,
+            results,
+            running: RefCell::new(HashSet::new()),
+            fresh: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// Queues `check` unless it is already running or answered this round.
+    fn request(&self, check: SlowCheck) {
+        if self.fresh.borrow().contains(&check) || self.running.borrow().contains(&check) {
+            return;
+        }
+        if self.jobs.send(check.clone()).is_ok() {
+            self.running.borrow_mut().insert(check);
+        }
+    }
+}
+
+/// Everything conditions look at, shared by all apps for one evaluation
+/// round. Each source is only gathered when a condition first asks for it, and
+/// remembered until the next `refresh`, so a supervisor running all session
+/// doesn't probe the internet or scan USB for apps that never ask.
+pub struct ConditionCache {
+    pub now: NaiveDateTime,
+    pub uptime: u64,
+    /// Only known to the daemon; the GUI leaves it unset.
+    pub since_login: Option<Duration>,
+    pub session_type: Option<String>,
+    /// `XDG_CURRENT_DESKTOP` entries plus `XDG_SESSION_DESKTOP`, lowercased.
+    pub desktops: Vec<String>,
+    connectivity: ConnectivityConfig,
+    network: OnceCell<NetworkState>,
+    mounts: OnceCell<Vec<MountedFs>>,
+    system: RefCell<System>,
+    processes_fresh: Cell<bool>,
+    power: OnceCell<PowerState>,
+    load: OnceCell<SystemLoad>,
+    usb_devices: OnceCell<Vec<UsbDeviceInfo>>,
+    displays: OnceCell<Vec<DisplayInfo>>,
+    local_network: OnceCell<LocalNetwork>,
+    hosts: RefCell<HashMap<(String, Option<u16>), bool>>,
+    commands: RefCell<HashMap<(String, u64), CommandResult>>,
+    fs_space: RefCell<HashMap<PathBuf, Option<FsSpace>>>,
+    paths: RefCell<HashMap<(PathBuf, PathRequirements), bool>>,
+    values: RefCell<HashMap<PathBuf, Option<String>>>,
+    /// Set for the GUI. Without it slow checks run inline, which is what the
+    /// daemon wants.
+    worker: Option<CheckWorker>,
+}
+
+impl Default for ConditionCache {
+    fn default() -> Self {
+        Self {
+            now: Local::now().naive_local(),
+            uptime: System::uptime(),
+            since_login: None,
+            session_type: env::var("XDG_SESSION_TYPE").ok(),
+            desktops: current_desktops(),
+            connectivity: ConnectivityConfig::default(),
+            network: OnceCell::new(),
+            mounts: OnceCell::new(),
+            system: RefCell::new(System::new()),
+            processes_fresh: Cell::new(false),
+            power: OnceCell::new(),
+            load: OnceCell::new(),
+            usb_devices: OnceCell::new(),
+            displays: OnceCell::new(),
+            local_network: OnceCell::new(),
+            hosts: RefCell::new(HashMap::new()),
+            commands: RefCell::new(HashMap::new()),
+            fs_space: RefCell::new(HashMap::new()),
+            paths: RefCell::new(HashMap::new()),
+            values: RefCell::new(HashMap::new()),
+            worker: None,
+        }
+    }
+}
+
+impl ConditionCache {
+    /// A cache that runs host probes and commands in the background. `on_result` is
+    /// called from the worker thread whenever a result is ready.
+    pub fn with_background_checks(on_result: impl Fn() + Send + 'static) -> Self {
+        Self {
+            worker: Some(CheckWorker::spawn(on_result)),
+            ..Self::default()
+        }
+    }
+
+    /// Moves finished background checks into the cache.
+    pub fn collect_background_results(&self) {
+        let Some(worker) = &self.worker else {
+            return;
+        };
+        for (check, result) in worker.results.try_iter() {
+            match (&check, result) {
+                (SlowCheck::Host { host, port, .. }, SlowResult::Host(reachable)) => {
+                    self.hosts
+                        .borrow_mut()
+                        .insert((host.clone(), *port), reachable);
+                }
+                (
+                    SlowCheck::Command {
+                        command,
+                        timeout_ms,
+                    },
+                    SlowResult::Command(result),
+                ) => {
+                    self.commands
+                        .borrow_mut()
+                        .insert((command.clone(), *timeout_ms), result);
+                }
+                _ => unreachable!("the worker answers each check with its own result"),
+            }
+            worker.running.borrow_mut().remove(&check);
+            worker.fresh.borrow_mut().insert(check);
+        }
+    }
+
+    /// Starts a new round: everything is gathered again when next asked for.
+    pub fn refresh(&mut self, connectivity: &ConnectivityConfig) {
+        self.now = Local::now().naive_local();
+        self.uptime = System::uptime();
+        self.connectivity.clone_from(connectivity);
+        self.network.take();
+        self.mounts.take();
+        self.processes_fresh.set(false);
+        self.power.take();
+        self.load.take();
+        self.usb_devices.take();
+        self.displays.take();
+        self.local_network.take();
+        if let Some(worker) = &mut self.worker {
+            // Keep showing the last results while the checks run again.
+            worker.fresh.get_mut().clear();
+        } else {
+            self.hosts.get_mut().clear();
+            self.commands.get_mut().clear();
+        }
+        self.fs_space.get_mut().clear();
+        self.paths.get_mut().clear();
+        self.values.get_mut().clear();
+    }
+
+    pub fn network(&self, os_ops: &dyn OsOperations) -> NetworkState {
+        *self
+            .network
+            .get_or_init(|| os_ops.check_internet_connection(&self.connectivity))
+    }
+
+    pub fn mounts(&self, os_ops: &dyn OsOperations) -> &[MountedFs] {
+        self.mounts.get_or_init(|| os_ops.mounted_filesystems())
+    }
+
+    /// Processes with their executable and command line.
+    pub fn system(&self) -> Ref<'_, System> {
+        if !self.processes_fresh.replace(true) {
+            self.system.borrow_mut().refresh_processes_specifics(
+                ProcessesToUpdate::All,
+                true,
+                ProcessRefreshKind::nothing()
+                    .with_exe(UpdateKind::OnlyIfNotSet)
+                    .with_cmd(UpdateKind::OnlyIfNotSet),
+            );
+        }
+        self.system.borrow()
+    }
+
+    pub fn power(&self, os_ops: &dyn OsOperations) -> &PowerState {
+        self.power.get_or_init(|| os_ops.power_state())
+    }
+
+    pub fn load(&self, os_ops: &dyn OsOperations) -> &SystemLoad {
+        self.load.get_or_init(|| os_ops.system_load())
+    }
+
+    pub fn usb_devices(&self, os_ops: &dyn OsOperations) -> &[UsbDeviceInfo] {
+        self.usb_devices.get_or_init(|| os_ops.get_usb_devices())
+    }
+
+    pub fn displays(&self, os_ops: &dyn OsOperations) -> &[DisplayInfo] {
+        self.displays.get_or_init(|| os_ops.get_displays())
+    }
+
+    pub fn local_network(&self, os_ops: &dyn OsOperations) -> &LocalNetwork {
+        self.local_network.get_or_init(|| os_ops.local_network())
+    }
+
+    /// `None` while a background probe hasn't answered yet.
+    fn is_host_reachable(
+        &self,
+        os_ops: &dyn OsOperations,
+        host: &str,
+        port: Option<u16>,
+        timeout_ms: u64,
+    ) -> Option<bool> {
+        let key = (host.to_string(), port);
+        if let Some(worker) = &self.worker {
+            worker.request(SlowCheck::Host {
+                host: host.to_string(),
+                port,
+                timeout_ms,
+            });
+            return self.hosts.borrow().get(&key).copied();
+        }
+        if let Some(&reachable) = self.hosts.borrow().get(&key) {
+            return Some(reachable);
+        }
+        let reachable = os_ops.is_host_reachable(host, port, Duration::from_millis(timeout_ms));
+        self.hosts.borrow_mut().insert(key, reachable);
+        Some(reachable)
+    }
+
+    /// `None` when the mount didn't answer in time.
+    fn fs_space(&self, os_ops: &dyn OsOperations, mount_point: &Path) -> Option<FsSpace> {
+        if let Some(&space) = self.fs_space.borrow().get(mount_point) {
+            return space;
+        }
+        let space = os_ops.fs_space(mount_point, FS_RESPONSE_TIMEOUT);
+        self.fs_space
+            .borrow_mut()
+            .insert(mount_point.to_path_buf(), space);
+        space
+    }
+
+    fn check_path(
+        &self,
+        os_ops: &dyn OsOperations,
+        path: &Path,
+        require: PathRequirements,
+    ) -> bool {
+        let key = (path.to_path_buf(), require);
+        if let Some(&ok) = self.paths.borrow().get(&key) {
+            return ok;
+        }
+        let ok = os_ops.check_path(path, require, FS_RESPONSE_TIMEOUT);
+        self.paths.borrow_mut().insert(key, ok);
+        ok
+    }
+
+    fn read_value(&self, os_ops: &dyn OsOperations, path: &Path) -> Option<String> {
+        if let Some(value) = self.values.borrow().get(path) {
+            return value.clone();
+        }
+        let value = os_ops.read_value(path, FS_RESPONSE_TIMEOUT);
+        self.values
+            .borrow_mut()
+            .insert(path.to_path_buf(), value.clone());
+        value
+    }
+
+    /// `None` while a background command hasn't finished yet.
+    fn run_command(
+        &self,
+        os_ops: &dyn OsOperations,
+        command: &str,
+        timeout_ms: u64,
+    ) -> Option<CommandResult> {
+        let key = (command.to_string(), timeout_ms);
+        if let Some(worker) = &self.worker {
+            worker.request(SlowCheck::Command {
+                command: command.to_string(),
+                timeout_ms,
+            });
+            return self.commands.borrow().get(&key).cloned();
+        }
+        if let Some(result) = self.commands.borrow().get(&key) {
+            return Some(result.clone());
+        }
+        let result = os_ops.run_check_command(command, Duration::from_millis(timeout_ms));
+        self.commands.borrow_mut().insert(key, result.clone());
+        Some(result)
+    }
+}
+
+fn current_desktops() -> Vec<String> {
+    let current = env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+    let session = env::var("XDG_SESSION_DESKTOP").unwrap_or_default();
+    current
+        .split(':')
+        .chain([session.as_str()])
+        .filter(|d| !d.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Why a mounted partition falls short of `require`, if it does.
+fn partition_problem(
     os_ops: &dyn OsOperations,
-    app: &AppConfig,
-    has_internet: bool,
-    disks: &Disks,
+    cache: &ConditionCache,
+    mount_point: &Path,
+    require: &PartitionRequirements,
+) -> Option<String> {
+    // Space is only asked for when needed, and never without a timeout.
+    let space = (require.responsive || require.min_free_gb.is_some())
+        .then(|| cache.fs_space(os_ops, mount_point));
+    if space == Some(None) {
+        return Some("Not responding".to_string());
+    }
+    if require.read_write {
+        // The last mount there is the one in effect.
+        match cache
+            .mounts(os_ops)
+            .iter()
+            .rfind(|m| m.mount_point == mount_point)
+        {
+            Some(mount) if mount.read_only => return Some("Mounted read-only".to_string()),
+            Some(_) => {}
+            None => return Some("Mount options are unknown".to_string()),
+        }
+    }
+    if let (Some(gb), Some(Some(space))) = (require.min_free_gb, space)
+        && space.available < gb.saturating_mul(1_000_000_000)
+    {
+        return Some(format!(
+            "Only {:.1} GB free",
+            space.available as f64 / 1_000_000_000.0
+        ));
+    }
+    None
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn evaluate_condition(
+    os_ops: &dyn OsOperations,
+    condition: &Condition,
+    cache: &ConditionCache,
 ) -> ConditionStatus {
-    let partition_ok = app
-        .conditions
-        .partition_mounted
-        .as_ref()
-        .is_none_or(|p| os_ops.is_partition_mounted(p, disks));
-    let internet_ok = !app.conditions.internet || has_internet;
+    let evaluate_all = |conditions: &[Condition]| {
+        conditions
+            .iter()
+            .map(|c| evaluate_condition(os_ops, c, cache))
+            .collect::<Vec<_>>()
+    };
+
+    let mut mount_point = None;
+    let mut detail = None;
+    let mut pending = false;
+    let (ok, children) = match condition {
+        Condition::All { conditions } => {
+            let children = evaluate_all(conditions);
+            let ok = children.iter().all(|c| c.ok);
+            // Still open unless something has failed for sure.
+            pending = !ok && children.iter().all(|c| c.ok || c.pending);
+            (ok, children)
+        }
+        Condition::Any { conditions } => {
+            let children = evaluate_all(conditions);
+            let ok = children.iter().any(|c| c.ok);
+            pending = !ok && children.iter().any(|c| c.pending);
+            (ok, children)
+        }
+        Condition::Not { condition } => {
+            let child = evaluate_condition(os_ops, condition, cache);
+            pending = child.pending;
+            (!child.ok && !child.pending, vec![child])
+        }
+        Condition::Internet {
+            allow_captive_portal,
+        } => {
+            let ok = match cache.network(os_ops) {
+                NetworkState::Online => true,
+                NetworkState::CaptivePortal => *allow_captive_portal,
+                NetworkState::Offline => false,
+            };
+            (ok, Vec::new())
+        }
+        Condition::PartitionMounted { partition, require } => {
+            mount_point = os_ops.partition_mount_point(partition, cache.mounts(os_ops));
+            let problem = mount_point
+                .as_deref()
+                .and_then(|mount_point| partition_problem(os_ops, cache, mount_point, require));
+            let ok = mount_point.is_some() && problem.is_none();
+            detail = problem;
+            (ok, Vec::new())
+        }
+        Condition::OnAcPower => (cache.power(os_ops).on_ac, Vec::new()),
+        Condition::BatteryAbove { percent } => (
+            cache
+                .power(os_ops)
+                .battery_percent
+                .is_none_or(|p| p > *percent),
+            Vec::new(),
+        ),
+        Condition::NotPowerSaver => (!cache.power(os_ops).power_saver, Vec::new()),
+        Condition::Session {
+            session_type,
+            desktops,
+            not_desktops,
+        } => {
+            let in_desktop = |names: &[String]| {
+                names
+                    .iter()
+                    .any(|name| cache.desktops.contains(&name.to_lowercase()))
+            };
+            let type_ok = session_type.as_ref().is_none_or(|wanted| {
+                cache
+                    .session_type
+                    .as_ref()
+                    .is_some_and(|current| current.eq_ignore_ascii_case(wanted))
+            });
+            (
+                type_ok
+                    && (desktops.is_empty() || in_desktop(desktops))
+                    && !in_desktop(not_desktops),
+                Vec::new(),
+            )
+        }
+        Condition::Command {
+            command,
+            timeout_ms,
+        } => match cache.run_command(os_ops, command, *timeout_ms) {
+            Some(result) => {
+                if !result.success && !result.stderr.is_empty() {
+                    detail = Some(result.stderr);
+                }
+                (result.success, Vec::new())
+            }
+            None => {
+                pending = true;
+                (false, Vec::new())
+            }
+        },
+        Condition::UptimeAtLeast { seconds } => (cache.uptime >= *seconds, Vec::new()),
+        Condition::SystemIdle {
+            max_load,
+            max_cpu_pressure,
+            max_io_pressure,
+            max_wait,
+        } => {
+            let below = |limit: &Option<f64>, value: Option<f64>| match (limit, value) {
+                (Some(limit), Some(value)) => value < *limit,
+                _ => true,
+            };
+            let load = cache.load(os_ops);
+            let idle = below(max_load, load.load1)
+                && below(max_cpu_pressure, load.cpu_pressure)
+                && below(max_io_pressure, load.io_pressure);
+            let waited_enough = max_wait
+                .zip(cache.since_login)
+                .is_some_and(|(wait, since)| since >= Duration::from_secs(wait));
+            (idle || waited_enough, Vec::new())
+        }
+        Condition::Schedule { schedule } => (schedule::is_open(schedule, cache.now), Vec::new()),
+        Condition::ProcessRunning { process } => (
+            os_ops.is_process_running(process, &cache.system()),
+            Vec::new(),
+        ),
+        Condition::ProcessAbsent { process } => (
+            !os_ops.is_process_running(process, &cache.system()),
+            Vec::new(),
+        ),
+        Condition::FileValue { path, compare } => {
+            let value = cache.read_value(os_ops, &expand_home(path));
+            let ok = value.as_deref().is_some_and(|v| compare.is_match(v));
+            if !ok {
+                detail = Some(match value {
+                    Some(value) => format!("Current value: {value}"),
+                    None => "Can't be read".to_string(),
+                });
+            }
+            (ok, Vec::new())
+        }
+        Condition::PathExists { path, require } => {
+            let ok = cache.check_path(os_ops, &expand_home(path), *require);
+            (ok, Vec::new())
+        }
+        Condition::UsbDevice { device } => (
+            cache.usb_devices(os_ops).iter().any(|d| d.matches(device)),
+            Vec::new(),
+        ),
+        Condition::DisplaysAtLeast { count } => {
+            (cache.displays(os_ops).len() >= *count, Vec::new())
+        }
+        Condition::DisplayConnected { display } => (
+            cache.displays(os_ops).iter().any(|d| d.matches(display)),
+            Vec::new(),
+        ),
+        Condition::OnNetwork { network } => {
+            (cache.local_network(os_ops).matches(network), Vec::new())
+        }
+        Condition::HostReachable {
+            host,
+            port,
+            timeout_ms,
+        } => {
+            let reachable = cache.is_host_reachable(os_ops, host, *port, *timeout_ms);
+            pending = reachable.is_none();
+            (reachable.unwrap_or(false), Vec::new())
+        }
+    };
+
     ConditionStatus {
-        internet_ok,
-        partition_ok,
+        label: condition.to_string(),
+        ok,
+        pending,
+        children,
+        mount_point,
+        detail,
     }
 }
 
-pub fn perform_launch_checks(os_ops: &dyn OsOperations, managed_apps: &mut [AppConfig]) {
-    let has_internet = os_ops.check_internet_connection();
-    let mut disks = Disks::new();
-    disks.refresh(true);
+pub fn check_app_conditions(
+    os_ops: &dyn OsOperations,
+    app: &AppConfig,
+    cache: &ConditionCache,
+) -> ConditionStatus {
+    evaluate_condition(os_ops, &app.launch_condition(), cache)
+}
 
+/// Whether a running app with `stop_when_unmet` may keep running.
+pub fn check_stop_conditions(
+    os_ops: &dyn OsOperations,
+    app: &AppConfig,
+    cache: &ConditionCache,
+) -> ConditionStatus {
+    evaluate_condition(os_ops, &app.stop_condition(), cache)
+}
+
+/// `cache` is expected to be refreshed for this round. `since_login` holds
+/// back apps whose `delay_after_login` hasn't passed. Returns the children of
+/// the apps launched this round, by app name.
+pub fn perform_launch_checks(
+    os_ops: &dyn OsOperations,
+    cache: &ConditionCache,
+    managed_apps: &mut [AppConfig],
+    since_login: Duration,
+) -> Vec<(String, Child)> {
+    let mut children = Vec::new();
     for app in managed_apps.iter_mut() {
-        if app.launched {
+        if app.launched
+            || app
+                .delay_after_login
+                .is_some_and(|delay| since_login < Duration::from_secs(delay))
+        {
             continue;
         }
 
-        let status = check_app_conditions(os_ops, app, has_internet, &disks);
+        let status = check_app_conditions(os_ops, app, cache);
 
-        if status.internet_ok && status.partition_ok {
-            os_ops.launch_app(app);
+        if status.ok {
+            if let Some(child) = os_ops.launch_app(app, status.mount_point()) {
+                children.push((app.name.clone(), child));
+            }
             app.launched = true;
         }
     }
+    children
 }
 
 pub struct ConditionalLauncherApp {
     pub apps: Vec<AppConfig>,
     pub os_ops: Box<dyn OsOperations>,
     pub available_partitions: Vec<PartitionInfo>,
+    pub connectivity: ConnectivityConfig,
+    /// Why the config file couldn't be loaded or saved. The GUI shows it and
+    /// stops editing, so the file isn't overwritten with defaults.
+    pub config_error: Option<String>,
 }
 
 pub fn load_all_apps(os_ops: &dyn OsOperations) -> Vec<AppConfig> {
-    let mut managed_apps = ConditionalLauncherApp::load_config();
+    let mut managed_apps = ConditionalLauncherApp::load_config()
+        .map(|config| config.apps)
+        .unwrap_or_default();
     for app in &mut managed_apps {
         app.is_managed = true;
     }
@@ -65,28 +717,37 @@ pub fn load_all_apps(os_ops: &dyn OsOperations) -> Vec<AppConfig> {
         }
     }
 
-    apps.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    apps.sort_by_key(|a| a.name.to_lowercase());
     apps
 }
 
 impl ConditionalLauncherApp {
-    pub fn load_config() -> Vec<AppConfig> {
-        Config::load_config()
+    pub fn load_config() -> Result<Config, String> {
+        Config::load()
     }
 
     pub fn new(apps: Vec<AppConfig>) -> Self {
         let os_ops = crate::os::get_os_operations();
         let available_partitions = os_ops.get_partitions();
+        let (connectivity, config_error) = match Self::load_config() {
+            Ok(config) => (config.connectivity, None),
+            Err(error) => (ConnectivityConfig::default(), Some(error)),
+        };
 
         Self {
             apps,
             os_ops,
             available_partitions,
+            connectivity,
+            config_error,
         }
     }
 
     pub fn save_config(&mut self) {
-        Config::save_config(&self.apps);
+        if let Err(error) = Config::save_config(&self.apps) {
+            self.config_error = Some(error);
+            return;
+        }
 
         let managed_app_count = self.apps.iter().filter(|a| a.is_managed).count();
         if managed_app_count == 0 {
@@ -96,3 +757,67 @@ impl ConditionalLauncherApp {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn background_checks_are_pending_until_they_answer() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let condition = Condition::Not {
+            condition: Box::new(Condition::HostReachable {
+                host: "127.0.0.1".to_string(),
+                port: Some(listener.local_addr().unwrap().port()),
+                timeout_ms: 1000,
+            }),
+        };
+        let (answered, wait) = mpsc::channel();
+        let cache = ConditionCache::with_background_checks(move || {
+            let _ = answered.send(());
+        });
+        let os_ops = crate::os::get_os_operations();
+
+        let status = evaluate_condition(os_ops.as_ref(), &condition, &cache);
+        assert!(!status.ok && status.pending);
+        assert!(status.children[0].pending);
+
+        wait.recv_timeout(Duration::from_secs(5)).unwrap();
+        cache.collect_background_results();
+        let status = evaluate_condition(os_ops.as_ref(), &condition, &cache);
+        assert!(!status.ok && !status.pending);
+        assert!(status.children[0].ok);
+    }
+
+    #[test]
+    fn background_commands_keep_their_stderr() {
+        let condition = Condition::Command {
+            command: "echo offline >&2; false".to_string(),
+            timeout_ms: 5000,
+        };
+        let (answered, wait) = mpsc::channel();
+        let mut cache = ConditionCache::with_background_checks(move || {
+            let _ = answered.send(());
+        });
+        let os_ops = crate::os::get_os_operations();
+
+        assert!(evaluate_condition(os_ops.as_ref(), &condition, &cache).pending);
+        wait.recv_timeout(Duration::from_secs(5)).unwrap();
+        cache.collect_background_results();
+        let status = evaluate_condition(os_ops.as_ref(), &condition, &cache);
+        assert!(!status.ok && !status.pending);
+        assert_eq!(status.detail.as_deref(), Some("offline"));
+
+        // A new round runs the command again but keeps showing the last result.
+        let offline = ConnectivityConfig {
+            probes: Vec::new(),
+            ..ConnectivityConfig::default()
+        };
+        cache.refresh(&offline);
+        let status = evaluate_condition(os_ops.as_ref(), &condition, &cache);
+        assert!(!status.pending);
+        assert_eq!(status.detail.as_deref(), Some("offline"));
+        wait.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+}
@@ -1,5 +1,11 @@
+use chrono::{DateTime, Local};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::fs;
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::num::NonZeroU64;
 use std::path::PathBuf;
 
 mod path_serde {
@@ -29,9 +35,305 @@ mod path_serde {
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct Config {
+    #[serde(default)]
+    pub connectivity: ConnectivityConfig,
+    #[serde(default)]
+    pub supervisor: SupervisorConfig,
+    #[serde(default)]
     pub apps: Vec<AppConfig>,
 }
 
+/// What supervisor mode does when an app exits on its own. An app that isn't
+/// restarted is launched again once its conditions fail and come back.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    Never,
+    /// Only after a non-zero exit status or a signal, so quitting the app
+    /// keeps it closed.
+    #[default]
+    OnFailure,
+    Always,
+}
+
+fn default_max_restarts() -> u32 {
+    5
+}
+
+fn default_restart_window_secs() -> u64 {
+    600
+}
+
+/// Restarts back off exponentially; after `max_restarts` within
+/// `window_secs` the next one waits until the oldest has left the window.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RestartConfig {
+    #[serde(default)]
+    pub policy: RestartPolicy,
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+    #[serde(default = "default_restart_window_secs")]
+    pub window_secs: u64,
+}
+
+impl Default for RestartConfig {
+    fn default() -> Self {
+        Self {
+            policy: RestartPolicy::default(),
+            max_restarts: default_max_restarts(),
+            window_secs: default_restart_window_secs(),
+        }
+    }
+}
+
+impl RestartConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Standard 5-field cron expression in local time, e.g. `0 4 * * *`. A
+/// leading seconds field is accepted too. Weekdays count from Sunday as 0 (or
+/// 7), as in crontab. Parsed when the config is loaded, so a typo is an error
+/// rather than a restart that never happens.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronSchedule {
+    expression: String,
+    schedule: Box<cron::Schedule>,
+}
+
+impl CronSchedule {
+    pub fn next_after(&self, from: &DateTime<Local>) -> Option<DateTime<Local>> {
+        self.schedule.after(from).next()
+    }
+}
+
+impl TryFrom<String> for CronSchedule {
+    type Error = String;
+
+    fn try_from(expression: String) -> Result<Self, Self::Error> {
+        let invalid = |e: &dyn fmt::Display| format!("invalid cron expression {expression:?}: {e}");
+        // The `cron` crate wants a seconds field first.
+        let mut fields: Vec<String> = expression.split_whitespace().map(String::from).collect();
+        if fields.len() == 5 {
+            fields.insert(0, "0".to_string());
+        }
+        if let Some(weekdays) = fields.get_mut(5) {
+            *weekdays = crate_weekdays(weekdays).map_err(|e| invalid(&e))?;
+        }
+        let schedule = fields
+            .join(" ")
+            .parse()
+            .map(Box::new)
+            .map_err(|e| invalid(&e))?;
+        Ok(CronSchedule {
+            expression,
+            schedule,
+        })
+    }
+}
+
+/// Rewrites a crontab day-of-week field (0-7, Sunday being 0 and 7) into the
+/// `cron` crate's numbering, 1-7 starting on Sunday. Names like `MON-FRI`
+/// are the same in both and pass through.
+fn crate_weekdays(field: &str) -> Result<String, String> {
+    let number = |n: &str| n.parse::<u32>().ok();
+    let items = field.split(',').map(|item| {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (item, None),
+        };
+        let bounds = match range.split_once('-') {
+            _ if range == "*" || range == "?" => step.is_some().then_some((0, 6)),
+            Some((first, last)) => number(first).zip(number(last)),
+            None => number(range).map(|day| (day, day)),
+        };
+        let Some((first, last)) = bounds else {
+            return Ok(item.to_string());
+        };
+        if last > 7 || first > last {
+            return Err(format!("day of week {range:?} is out of 0-7"));
+        }
+        let step = match step {
+            Some(step) => number(step)
+                .filter(|&step| step > 0)
+                .ok_or_else(|| format!("invalid step {step:?}"))?,
+            None => 1,
+        };
+        let mut days: Vec<u32> = (first..=last)
+            .step_by(step as usize)
+            .map(|day| day % 7 + 1)
+            .collect();
+        days.sort_unstable();
+        days.dedup();
+        Ok(days
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join(","))
+    });
+    Ok(items.collect::<Result<Vec<_>, String>>()?.join(","))
+}
+
+impl From<CronSchedule> for String {
+    fn from(cron: CronSchedule) -> Self {
+        cron.expression
+    }
+}
+
+impl PartialEq for CronSchedule {
+    fn eq(&self, other: &Self) -> bool {
+        self.expression == other.expression
+    }
+}
+
+/// When a scheduled restart is due.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartSchedule {
+    Cron(CronSchedule),
+    /// This many hours after the app was started. Zero is rejected, it would
+    /// restart the app every round.
+    EveryHours(NonZeroU64),
+}
+
+/// Periodic graceful restarts in supervisor mode, e.g. for apps that leak
+/// memory.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScheduledRestart {
+    #[serde(flatten)]
+    pub when: RestartSchedule,
+    /// Skip a restart when the app was started less than this many minutes
+    /// before it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip_if_started_within_mins: Option<u64>,
+}
+
+/// What the watchdog does with an app over its limits.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchdogAction {
+    /// Only send a desktop notification.
+    #[default]
+    Notify,
+    /// Stop it gracefully and launch it again once its conditions hold.
+    Restart,
+    /// Kill it and leave it alone until the next login.
+    Kill,
+}
+
+fn default_cpu_minutes() -> u64 {
+    5
+}
+
+/// Resource limits checked in supervisor mode, summed over the app's whole
+/// process group.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Watchdog {
+    /// Resident memory in megabytes (millions of bytes, not MiB).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_memory_mb: Option<u64>,
+    /// CPU usage in percent of one core, so it can go above 100.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cpu_percent: Option<f32>,
+    /// How long CPU usage has to stay above `max_cpu_percent`.
+    #[serde(default = "default_cpu_minutes")]
+    pub cpu_minutes: u64,
+    #[serde(default)]
+    pub action: WatchdogAction,
+}
+
+/// How the background process behaves once it has started everything.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SupervisorConfig {
+    /// Keep running after every app has launched: launch apps whose
+    /// conditions only hold later, and launch them again after they exit.
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+fn default_probe_timeout_ms() -> u64 {
+    3000
+}
+
+fn default_command_timeout_ms() -> u64 {
+    5000
+}
+
+/// A single check used to decide whether the machine is online.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Probe {
+    /// GET `url` without following redirects. A redirect, a 200 where a 204
+    /// is expected, or a body lacking `expected_body` means a captive portal.
+    Http {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expected_status: Option<u16>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expected_body: Option<String>,
+        #[serde(default = "default_probe_timeout_ms")]
+        timeout_ms: u64,
+    },
+    /// Resolve `host` through the system resolver, or through `server`
+    /// (`ip` or `ip:port`) when set.
+    Dns {
+        host: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        server: Option<String>,
+        #[serde(default = "default_probe_timeout_ms")]
+        timeout_ms: u64,
+    },
+    Tcp {
+        host: String,
+        port: u16,
+        #[serde(default = "default_probe_timeout_ms")]
+        timeout_ms: u64,
+    },
+}
+
+/// How many probes have to pass for the connection to count as online.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeRule {
+    #[default]
+    Any,
+    All,
+    AtLeast(usize),
+}
+
+impl ProbeRule {
+    pub fn is_satisfied(self, passed: usize, total: usize) -> bool {
+        match self {
+            ProbeRule::Any => passed > 0,
+            ProbeRule::All => total > 0 && passed == total,
+            ProbeRule::AtLeast(count) => passed >= count,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConnectivityConfig {
+    #[serde(default)]
+    pub pass: ProbeRule,
+    pub probes: Vec<Probe>,
+}
+
+impl Default for ConnectivityConfig {
+    fn default() -> Self {
+        Self {
+            pass: ProbeRule::Any,
+            probes: vec![Probe::Http {
+                url: "http://connectivitycheck.gstatic.com/generate_204".to_string(),
+                expected_status: Some(204),
+                expected_body: None,
+                timeout_ms: default_probe_timeout_ms(),
+            }],
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct AppConfig {
     pub name: String,
@@ -43,17 +345,845 @@ pub struct AppConfig {
     pub icon: Option<String>,
     #[serde(with = "path_serde", default)]
     pub working_dir: Option<PathBuf>,
+    /// Seconds to hold the app back after login, to stagger heavy apps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_after_login: Option<u64>,
+    /// In supervisor mode, stop the app once its conditions no longer hold.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stop_when_unmet: bool,
+    /// Seconds the conditions have to keep failing before the app is stopped,
+    /// so one probe that times out doesn't stop it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_after_secs: Option<u64>,
+    /// Seconds between asking the app to quit and killing it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_grace_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "RestartConfig::is_default")]
+    pub restart: RestartConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduled_restart: Option<ScheduledRestart>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watchdog: Option<Watchdog>,
+    /// `OnlyShowIn`/`NotShowIn` from the original desktop entry.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only_show_in: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub not_show_in: Vec<String>,
     #[serde(skip)]
     pub launched: bool,
     #[serde(skip)]
     pub is_managed: bool,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    All {
+        conditions: Vec<Condition>,
+    },
+    Any {
+        conditions: Vec<Condition>,
+    },
+    Not {
+        condition: Box<Condition>,
+    },
+    Internet {
+        /// Also pass behind a captive portal, which is usually not wanted.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        allow_captive_portal: bool,
+    },
+    UsbDevice {
+        #[serde(flatten)]
+        device: UsbDeviceRef,
+    },
+    /// At least `count` connected displays, built-in panel included.
+    DisplaysAtLeast {
+        count: usize,
+    },
+    DisplayConnected {
+        #[serde(flatten)]
+        display: DisplayRef,
+    },
+    PartitionMounted {
+        #[serde(flatten)]
+        partition: PartitionRef,
+        #[serde(flatten)]
+        require: PartitionRequirements,
+    },
+    /// Which network the machine is on, e.g. home or VPN.
+    OnNetwork {
+        #[serde(flatten)]
+        network: NetworkMatch,
+    },
+    /// TCP connect to `host:port`. Without a port, a refused connection also
+    /// counts, as it proves the host is up.
+    HostReachable {
+        host: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        port: Option<u16>,
+        #[serde(default = "default_probe_timeout_ms")]
+        timeout_ms: u64,
+    },
+    /// Passes on machines without a battery too.
+    OnAcPower,
+    /// Passes on machines without a battery too.
+    BatteryAbove {
+        percent: u8,
+    },
+    NotPowerSaver,
+    /// Matches `XDG_SESSION_TYPE` and, case-insensitively, any entry of
+    /// `XDG_CURRENT_DESKTOP` or `XDG_SESSION_DESKTOP`.
+    Session {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        session_type: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        desktops: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        not_desktops: Vec<String>,
+    },
+    UptimeAtLeast {
+        seconds: u64,
+    },
+    /// Every set threshold must hold. Pressures are the PSI "some avg10"
+    /// percentages. After `max_wait` seconds since login it passes anyway.
+    SystemIdle {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_load: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_cpu_pressure: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_io_pressure: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_wait: Option<u64>,
+    },
+    Schedule {
+        #[serde(flatten)]
+        schedule: Schedule,
+    },
+    ProcessRunning {
+        #[serde(flatten)]
+        process: ProcessMatch,
+    },
+    ProcessAbsent {
+        #[serde(flatten)]
+        process: ProcessMatch,
+    },
+    /// Runs `command` through `sh -c`; exit status 0 passes. Killed after
+    /// `timeout_ms`.
+    Command {
+        command: String,
+        #[serde(default = "default_command_timeout_ms")]
+        timeout_ms: u64,
+    },
+    /// Compares the trimmed contents of a file, typically in `/sys` or
+    /// `/proc`, e.g. lid state or a thermal zone's temperature.
+    FileValue {
+        path: String,
+        #[serde(flatten)]
+        compare: ValueMatch,
+    },
+    /// `path` may start with `~/`.
+    PathExists {
+        path: String,
+        #[serde(flatten)]
+        require: PathRequirements,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl fmt::Display for Weekday {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Weekday::Mon => "Mon",
+            Weekday::Tue => "Tue",
+            Weekday::Wed => "Wed",
+            Weekday::Thu => "Thu",
+            Weekday::Fri => "Fri",
+            Weekday::Sat => "Sat",
+            Weekday::Sun => "Sun",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Local wall-clock time, written as `"HH:MM"`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, PartialOrd)]
+#[serde(try_from = "String", into = "String")]
+pub struct ClockTime {
+    pub minutes: u16,
+}
+
+impl TryFrom<String> for ClockTime {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("invalid time {value:?}, expected HH:MM");
+        let (hours, minutes) = value.split_once(':').ok_or_else(invalid)?;
+        let hours: u16 = hours.parse().map_err(|_| invalid())?;
+        let minutes: u16 = minutes.parse().map_err(|_| invalid())?;
+        // 24:00 is allowed as the end of a day. Hours are checked first so
+        // the sum can't overflow.
+        if hours > 24 || minutes >= 60 || hours * 60 + minutes > 24 * 60 {
+            return Err(invalid());
+        }
+        Ok(ClockTime {
+            minutes: hours * 60 + minutes,
+        })
+    }
+}
+
+impl From<ClockTime> for String {
+    fn from(time: ClockTime) -> Self {
+        time.to_string()
+    }
+}
+
+impl fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.minutes / 60, self.minutes % 60)
+    }
+}
+
+/// Open on `days` (every day if empty) from `from` until `to`. A window with
+/// `to` before `from` runs past midnight and belongs to the day it opens.
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
-pub struct Conditions {
-    pub internet: bool,
+pub struct Schedule {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<ClockTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<ClockTime>,
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.days.is_empty() {
+            write!(f, "daily")?;
+        } else {
+            let days: Vec<_> = self.days.iter().map(Weekday::to_string).collect();
+            write!(f, "{}", days.join(","))?;
+        }
+        match (self.from, self.to) {
+            (None, None) => Ok(()),
+            (from, to) => write!(
+                f,
+                " {}-{}",
+                from.map(|t| t.to_string())
+                    .unwrap_or_else(|| "00:00".to_string()),
+                to.map(|t| t.to_string())
+                    .unwrap_or_else(|| "24:00".to_string()),
+            ),
+        }
+    }
+}
+
+/// What a file's trimmed contents are compared with.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueMatch {
+    Equals(String),
+    /// Regex searched in the contents.
+    Matches(Pattern),
+    /// Numeric comparisons; contents that aren't a number never pass.
+    Below(f64),
+    Above(f64),
+}
+
+impl ValueMatch {
+    pub fn is_match(&self, value: &str) -> bool {
+        let number = || value.parse::<f64>().ok();
+        match self {
+            ValueMatch::Equals(expected) => value == expected,
+            ValueMatch::Matches(pattern) => pattern.is_match(value),
+            ValueMatch::Below(limit) => number().is_some_and(|n| n < *limit),
+            ValueMatch::Above(limit) => number().is_some_and(|n| n > *limit),
+        }
+    }
+}
+
+impl fmt::Display for ValueMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueMatch::Equals(expected) => write!(f, "= \"{expected}\""),
+            ValueMatch::Matches(pattern) => write!(f, "matches /{pattern}/"),
+            ValueMatch::Below(limit) => write!(f, "< {limit}"),
+            ValueMatch::Above(limit) => write!(f, "> {limit}"),
+        }
+    }
+}
+
+/// A regex compiled when the config is loaded, so a typo is a parse error
+/// rather than a condition that silently never matches.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Regex::new(&value)
+            .map(Pattern)
+            .map_err(|e| format!("invalid regex {value:?}: {e}"))
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.0.as_str().to_string()
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.as_str())
+    }
+}
+
+/// How a process is recognized.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessMatch {
+    /// Exact process name, as in `ps -e`.
+    Name(String),
+    /// Full path of the executable.
+    Exe(String),
+    /// Regex searched in the space-joined command line.
+    Cmdline(Pattern),
+}
+
+impl fmt::Display for ProcessMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessMatch::Name(name) => write!(f, "{name}"),
+            ProcessMatch::Exe(exe) => write!(f, "{exe}"),
+            ProcessMatch::Cmdline(pattern) => write!(f, "/{pattern}/"),
+        }
+    }
+}
+
+/// Extra checks on top of a partition being mounted.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct PartitionRequirements {
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub partition_mounted: Option<String>,
+    pub min_free_gb: Option<u64>,
+    /// Not mounted read-only, e.g. after errors made the kernel remount it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_write: bool,
+    /// The mount answers a `statfs` in time.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub responsive: bool,
+}
+
+/// Extra checks on top of a path existing.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PathRequirements {
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_dir: bool,
+    /// A directory with entries, or a file longer than zero bytes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub non_empty: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub writable: bool,
+}
+
+impl Condition {
+    /// Conditions about when to start rather than about the app's
+    /// environment; they don't stop a running app.
+    pub fn only_gates_launch(&self) -> bool {
+        matches!(
+            self,
+            Condition::SystemIdle { .. } | Condition::UptimeAtLeast { .. }
+        )
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::All { .. } => write!(f, "All of"),
+            Condition::Any { .. } => write!(f, "Any of"),
+            Condition::Not { .. } => write!(f, "Not"),
+            Condition::Internet {
+                allow_captive_portal: false,
+            } => write!(f, "Internet"),
+            Condition::Internet {
+                allow_captive_portal: true,
+            } => write!(f, "Internet (captive portal allowed)"),
+            Condition::UsbDevice { device } => write!(f, "USB device {device}"),
+            Condition::OnNetwork { network } => write!(f, "On network: {network}"),
+            Condition::DisplaysAtLeast { count } => write!(f, "At least {count} displays"),
+            Condition::DisplayConnected { display } => write!(f, "Display {display}"),
+            Condition::PartitionMounted { partition, require } => {
+                write!(f, "Partition {partition} mounted")?;
+                if require.read_write {
+                    write!(f, " read-write")?;
+                }
+                if require.responsive {
+                    write!(f, ", responsive")?;
+                }
+                if let Some(gb) = require.min_free_gb {
+                    write!(f, ", {gb} GB free")?;
+                }
+                Ok(())
+            }
+            Condition::HostReachable {
+                host,
+                port: Some(port),
+                ..
+            } => write!(f, "Host {host}:{port} reachable"),
+            Condition::HostReachable { host, .. } => write!(f, "Host {host} reachable"),
+            Condition::OnAcPower => write!(f, "On AC power"),
+            Condition::BatteryAbove { percent } => write!(f, "Battery above {percent}%"),
+            Condition::NotPowerSaver => write!(f, "Not in power-saver mode"),
+            Condition::Session {
+                session_type,
+                desktops,
+                not_desktops,
+            } => {
+                let mut parts = Vec::new();
+                if let Some(session_type) = session_type {
+                    parts.push(session_type.clone());
+                }
+                if !desktops.is_empty() {
+                    parts.push(desktops.join("/"));
+                }
+                if !not_desktops.is_empty() {
+                    parts.push(format!("not {}", not_desktops.join("/")));
+                }
+                write!(f, "Session {}", parts.join(", "))
+            }
+            Condition::UptimeAtLeast { seconds } => write!(f, "Uptime at least {seconds}s"),
+            Condition::Command { command, .. } => write!(f, "Command `{command}`"),
+            Condition::FileValue { path, compare } => write!(f, "{path} {compare}"),
+            Condition::SystemIdle {
+                max_load,
+                max_cpu_pressure,
+                max_io_pressure,
+                max_wait,
+            } => {
+                let mut limits = Vec::new();
+                if let Some(load) = max_load {
+                    limits.push(format!("load < {load}"));
+                }
+                if let Some(cpu) = max_cpu_pressure {
+                    limits.push(format!("CPU pressure < {cpu}%"));
+                }
+                if let Some(io) = max_io_pressure {
+                    limits.push(format!("IO pressure < {io}%"));
+                }
+                write!(f, "System idle ({})", limits.join(", "))?;
+                if let Some(wait) = max_wait {
+                    write!(f, ", at most {wait}s after login")?;
+                }
+                Ok(())
+            }
+            Condition::Schedule { schedule } => write!(f, "Schedule {schedule}"),
+            Condition::ProcessRunning { process } => write!(f, "Process {process} running"),
+            Condition::ProcessAbsent { process } => write!(f, "Process {process} not running"),
+            Condition::PathExists { path, require } => {
+                let kind = if require.is_dir { "Directory" } else { "Path" };
+                write!(f, "{kind} {path} exists")?;
+                if require.non_empty {
+                    write!(f, ", non-empty")?;
+                }
+                if require.writable {
+                    write!(f, ", writable")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How a partition is identified. Anything but `Path` follows the partition
+/// to wherever it is mounted at the moment.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PartitionRef {
+    /// Mount point.
+    Path(String),
+    Uuid(String),
+    Partuuid(String),
+    Label(String),
+}
+
+impl fmt::Display for PartitionRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartitionRef::Path(path) => write!(f, "{path}"),
+            PartitionRef::Uuid(uuid) => write!(f, "UUID={uuid}"),
+            PartitionRef::Partuuid(partuuid) => write!(f, "PARTUUID={partuuid}"),
+            PartitionRef::Label(label) => write!(f, "LABEL={label}"),
+        }
+    }
+}
+
+/// How a USB device is recognised.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UsbDeviceRef {
+    /// `vendor:product`, e.g. `056a:0357`.
+    Id(String),
+    /// Case-insensitive substring of the device's product string.
+    Product(String),
+}
+
+impl fmt::Display for UsbDeviceRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsbDeviceRef::Id(id) => write!(f, "{id}"),
+            UsbDeviceRef::Product(product) => write!(f, "\"{product}\""),
+        }
+    }
+}
+
+/// A CIDR such as `192.168.1.0/24`, checked when the config is loaded.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(try_from = "String", into = "String")]
+pub struct Subnet {
+    cidr: String,
+    net: IpAddr,
+    prefix: u8,
+}
+
+impl Subnet {
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (addr, self.net) {
+            (IpAddr::V4(addr), IpAddr::V4(net)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(addr) & mask == u32::from(net) & mask
+            }
+            (IpAddr::V6(addr), IpAddr::V6(net)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(addr) & mask == u128::from(net) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl TryFrom<String> for Subnet {
+    type Error = String;
+
+    fn try_from(cidr: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("invalid subnet {cidr:?}, expected e.g. \"192.168.1.0/24\"");
+        let (net, prefix) = cidr.trim().split_once('/').ok_or_else(invalid)?;
+        let net: IpAddr = net.parse().map_err(|_| invalid())?;
+        let max_prefix = if net.is_ipv4() { 32 } else { 128 };
+        let prefix = prefix
+            .parse()
+            .ok()
+            .filter(|&prefix| prefix <= max_prefix)
+            .ok_or_else(invalid)?;
+        Ok(Subnet { cidr, net, prefix })
+    }
+}
+
+impl From<Subnet> for String {
+    fn from(subnet: Subnet) -> Self {
+        subnet.cidr
+    }
+}
+
+impl PartialEq for Subnet {
+    fn eq(&self, other: &Self) -> bool {
+        self.cidr == other.cidr
+    }
+}
+
+impl fmt::Display for Subnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.cidr)
+    }
+}
+
+/// A MAC address such as `a4:2b:b0:12:34:56`, checked when the config is
+/// loaded. Compared without regard to case.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct MacAddress(String);
+
+impl MacAddress {
+    pub fn matches(&self, mac: &str) -> bool {
+        self.0.trim().eq_ignore_ascii_case(mac)
+    }
+}
+
+impl TryFrom<String> for MacAddress {
+    type Error = String;
+
+    fn try_from(mac: String) -> Result<Self, Self::Error> {
+        let octets: Vec<_> = mac.trim().split(':').collect();
+        let valid = octets.len() == 6
+            && octets
+                .iter()
+                .all(|octet| octet.len() == 2 && u8::from_str_radix(octet, 16).is_ok());
+        if !valid {
+            return Err(format!(
+                "invalid MAC address {mac:?}, expected e.g. \"a4:2b:b0:12:34:56\""
+            ));
+        }
+        Ok(MacAddress(mac))
+    }
+}
+
+impl From<MacAddress> for String {
+    fn from(mac: MacAddress) -> Self {
+        mac.0
+    }
+}
+
+impl fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// How a network is recognised.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkMatch {
+    /// MAC address of the default gateway, e.g. the home router.
+    GatewayMac(MacAddress),
+    /// Matched against every interface's addresses.
+    Subnet(Subnet),
+    /// An interface such as `wg0` or `tun0` being up.
+    InterfaceUp(String),
+}
+
+impl fmt::Display for NetworkMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkMatch::GatewayMac(mac) => write!(f, "gateway {mac}"),
+            NetworkMatch::Subnet(subnet) => write!(f, "subnet {subnet}"),
+            NetworkMatch::InterfaceUp(interface) => write!(f, "{interface} up"),
+        }
+    }
+}
+
+/// How a display is recognised.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayRef {
+    /// DRM connector, e.g. `DP-1` or `HDMI-A-1`.
+    Connector(String),
+    /// Case-insensitive substring of the monitor name in its EDID.
+    Name(String),
+}
+
+impl fmt::Display for DisplayRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisplayRef::Connector(connector) => write!(f, "{connector}"),
+            DisplayRef::Name(name) => write!(f, "\"{name}\""),
+        }
+    }
+}
+
+/// Top-level conditions of an app. Every entry of `all` must hold; nested
+/// `any`/`not` nodes express anything more elaborate.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(from = "RawConditions")]
+pub struct Conditions {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub all: Vec<Condition>,
+}
+
+/// Accepts both the current format and the old `internet`/`partition_mounted`
+/// flags, so existing `managed_apps.toml` files keep working.
+#[derive(Deserialize)]
+struct RawConditions {
+    #[serde(default)]
+    all: Vec<Condition>,
+    #[serde(default)]
+    internet: bool,
+    #[serde(default)]
+    partition_mounted: Option<String>,
+}
+
+impl From<RawConditions> for Conditions {
+    fn from(raw: RawConditions) -> Self {
+        let mut conditions = Conditions { all: raw.all };
+        if raw.internet {
+            conditions.set_internet(true);
+        }
+        if let Some(path) = raw.partition_mounted {
+            conditions.set_partition_mounted(Some(PartitionRef::Path(path)));
+        }
+        conditions
+    }
+}
+
+impl AppConfig {
+    /// The user's conditions, followed by the desktop entry's session
+    /// restriction if it had one.
+    pub fn launch_condition(&self) -> Condition {
+        let mut conditions = self.conditions.all.clone();
+        if !self.only_show_in.is_empty() || !self.not_show_in.is_empty() {
+            conditions.push(Condition::Session {
+                session_type: None,
+                desktops: self.only_show_in.clone(),
+                not_desktops: self.not_show_in.clone(),
+            });
+        }
+        Condition::All { conditions }
+    }
+
+    /// What has to keep holding for `stop_when_unmet`: the launch condition
+    /// without top-level gates that only decide when to start. Otherwise an
+    /// app waiting for `system_idle` would be stopped by its own startup load.
+    pub fn stop_condition(&self) -> Condition {
+        let Condition::All { mut conditions } = self.launch_condition() else {
+            unreachable!("launch_condition is always an `all` node");
+        };
+        conditions.retain(|c| !c.only_gates_launch());
+        Condition::All { conditions }
+    }
+}
+
+impl Conditions {
+    pub fn is_empty(&self) -> bool {
+        self.all.is_empty()
+    }
+
+    pub fn internet(&self) -> bool {
+        self.all
+            .iter()
+            .any(|c| matches!(c, Condition::Internet { .. }))
+    }
+
+    pub fn set_internet(&mut self, enabled: bool) {
+        if enabled == self.internet() {
+            return;
+        }
+        if enabled {
+            self.all.insert(
+                0,
+                Condition::Internet {
+                    allow_captive_portal: false,
+                },
+            );
+        } else {
+            self.all
+                .retain(|c| !matches!(c, Condition::Internet { .. }));
+        }
+    }
+
+    pub fn partition_mounted(&self) -> Option<&PartitionRef> {
+        self.all.iter().find_map(|c| match c {
+            Condition::PartitionMounted { partition, .. } => Some(partition),
+            _ => None,
+        })
+    }
+
+    /// Picking another partition keeps the requirements.
+    pub fn set_partition_mounted(&mut self, partition: Option<PartitionRef>) {
+        let require = self.partition_requirements();
+        self.replace_first(
+            |c| matches!(c, Condition::PartitionMounted { .. }),
+            partition.map(|partition| Condition::PartitionMounted { partition, require }),
+        );
+    }
+
+    pub fn partition_requirements(&self) -> PartitionRequirements {
+        self.all
+            .iter()
+            .find_map(|c| match c {
+                Condition::PartitionMounted { require, .. } => Some(*require),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn set_partition_requirements(&mut self, new_require: PartitionRequirements) {
+        for condition in &mut self.all {
+            if let Condition::PartitionMounted { require, .. } = condition {
+                *require = new_require;
+                return;
+            }
+        }
+    }
+
+    pub fn on_ac_power(&self) -> bool {
+        self.all.contains(&Condition::OnAcPower)
+    }
+
+    pub fn set_on_ac_power(&mut self, enabled: bool) {
+        self.replace_first(
+            |c| *c == Condition::OnAcPower,
+            enabled.then_some(Condition::OnAcPower),
+        );
+    }
+
+    pub fn path_exists(&self) -> Option<(&str, PathRequirements)> {
+        self.all.iter().find_map(|c| match c {
+            Condition::PathExists { path, require } => Some((path.as_str(), *require)),
+            _ => None,
+        })
+    }
+
+    pub fn set_path_exists(&mut self, path: Option<(String, PathRequirements)>) {
+        self.replace_first(
+            |c| matches!(c, Condition::PathExists { .. }),
+            path.map(|(path, require)| Condition::PathExists { path, require }),
+        );
+    }
+
+    pub fn usb_device(&self) -> Option<&UsbDeviceRef> {
+        self.all.iter().find_map(|c| match c {
+            Condition::UsbDevice { device } => Some(device),
+            _ => None,
+        })
+    }
+
+    pub fn set_usb_device(&mut self, device: Option<UsbDeviceRef>) {
+        self.replace_first(
+            |c| matches!(c, Condition::UsbDevice { .. }),
+            device.map(|device| Condition::UsbDevice { device }),
+        );
+    }
+
+    /// Replaces or removes the first top-level condition of a kind, or
+    /// appends one if there is none yet.
+    fn replace_first(&mut self, matches: fn(&Condition) -> bool, condition: Option<Condition>) {
+        match (self.all.iter().position(matches), condition) {
+            (Some(i), Some(condition)) => self.all[i] = condition,
+            (Some(i), None) => {
+                self.all.remove(i);
+            }
+            (None, Some(condition)) => self.all.push(condition),
+            (None, None) => {}
+        }
+    }
 }
 
 impl Config {
@@ -63,22 +1193,228 @@ impl Config {
             .join("conditional-launcher/managed_apps.toml")
     }
 
-    pub fn load_config() -> Vec<AppConfig> {
-        fs::read_to_string(Self::config_path())
-            .ok()
-            .and_then(|toml_str| toml::from_str::<Config>(&toml_str).ok())
-            .map(|config| config.apps)
-            .unwrap_or_default()
+    /// A missing file is an empty config. One that doesn't parse is an error,
+    /// so a typo in a hand edit neither launches nothing silently nor gets
+    /// saved over.
+    pub fn load() -> Result<Config, String> {
+        let path = Self::config_path();
+        match fs::read_to_string(&path) {
+            Ok(toml_str) => toml::from_str(&toml_str)
+                .map_err(|e| format!("Could not parse {}: {e}", path.display())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!("Could not read {}: {e}", path.display())),
+        }
     }
 
-    pub fn save_config(apps: &[AppConfig]) {
+    /// Keeps the other sections of the file, and refuses to write when they
+    /// can't be read back.
+    pub fn save_config(apps: &[AppConfig]) -> Result<(), String> {
         let managed_apps: Vec<_> = apps.iter().filter(|a| a.is_managed).cloned().collect();
 
-        let config = Config { apps: managed_apps };
-        if let Some(parent) = Self::config_path().parent() {
+        let config = Config {
+            apps: managed_apps,
+            ..Self::load()?
+        };
+        let path = Self::config_path();
+        if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).ok();
         }
         let toml = toml::to_string_pretty(&config).unwrap();
-        fs::write(Self::config_path(), toml).ok();
+        fs::write(&path, toml).map_err(|e| format!("Could not write {}: {e}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(value: &str) -> Result<ClockTime, String> {
+        ClockTime::try_from(value.to_string())
+    }
+
+    #[test]
+    fn clock_time_parses_hh_mm() {
+        assert_eq!(time("00:00"), Ok(ClockTime { minutes: 0 }));
+        assert_eq!(time("9:05"), Ok(ClockTime { minutes: 545 }));
+        assert_eq!(time("23:59"), Ok(ClockTime { minutes: 1439 }));
+        assert_eq!(time("24:00"), Ok(ClockTime { minutes: 1440 }));
+        assert_eq!(time("09:05").unwrap().to_string(), "09:05");
+    }
+
+    #[test]
+    fn clock_time_rejects_out_of_range() {
+        for value in [
+            "24:01", "25:00", "12:60", "1100:00", "65535:59", "12", "ab:cd", "-1:00",
+        ] {
+            assert!(time(value).is_err(), "{value} should be rejected");
+        }
+    }
+
+    #[test]
+    fn value_match_compares_trimmed_contents() {
+        let compare = |toml_str: &str| toml::from_str::<ValueMatch>(toml_str).unwrap();
+
+        let equals = compare("equals = \"1\"");
+        assert!(equals.is_match("1"));
+        assert!(!equals.is_match("10"));
+
+        let matches = compare("matches = \"open$\"");
+        assert!(matches.is_match("state:      open"));
+        assert!(!matches.is_match("state:      closed"));
+        assert!(toml::from_str::<ValueMatch>("matches = \"open(\"").is_err());
+
+        let below = compare("below = 80000");
+        assert!(below.is_match("45000"));
+        assert!(!below.is_match("80000"));
+        assert!(!below.is_match("hot"));
+
+        let above = compare("above = 20.5");
+        assert!(above.is_match("21"));
+        assert!(!above.is_match("20.5"));
+    }
+
+    #[test]
+    fn scheduled_restart_is_validated_on_load() {
+        let restart = |toml_str: &str| toml::from_str::<ScheduledRestart>(toml_str);
+
+        let Ok(ScheduledRestart {
+            when: RestartSchedule::Cron(cron),
+            ..
+        }) = restart("cron = \"0 4 * * *\"")
+        else {
+            panic!("5-field cron should load");
+        };
+        let from = Local::now();
+        assert!(cron.next_after(&from).is_some_and(|at| at > from));
+        assert!(restart("cron = \"30 0 4 * * *\"").is_ok());
+        assert!(restart("cron = \"every day at four\"").is_err());
+
+        assert!(restart("cron = \"0 4 * * 8\"").is_err());
+
+        assert!(restart("every_hours = 12").is_ok());
+        assert!(restart("every_hours = 0").is_err());
+    }
+
+    #[test]
+    fn cron_weekdays_count_from_sunday() {
+        use chrono::{Datelike, TimeZone, Weekday::*};
+        // Saturday noon: a week from here meets each weekday once, Sunday first.
+        let from = Local.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let week = |expression: &str| {
+            let cron = CronSchedule::try_from(expression.to_string()).unwrap();
+            let mut days = Vec::new();
+            let mut at = from;
+            while let Some(next) = cron.next_after(&at)
+                && next < from + chrono::Days::new(7)
+            {
+                days.push(next.weekday());
+                at = next;
+            }
+            days
+        };
+
+        assert_eq!(week("0 4 * * 1"), [Mon]);
+        assert_eq!(week("0 4 * * 0"), [Sun]);
+        assert_eq!(week("0 4 * * 7"), [Sun]);
+        assert_eq!(week("0 4 * * 6"), [Sat]);
+        assert_eq!(week("0 4 * * 1-5"), [Mon, Tue, Wed, Thu, Fri]);
+        assert_eq!(week("0 4 * * 5-7"), [Sun, Fri, Sat]);
+        assert_eq!(week("0 4 * * */3"), [Sun, Wed, Sat]);
+        assert_eq!(week("0 4 * * MON-FRI"), [Mon, Tue, Wed, Thu, Fri]);
+        assert_eq!(week("0 0 4 * * 0,3"), [Sun, Wed]);
+    }
+
+    #[test]
+    fn network_matches_are_validated_on_load() {
+        let network = |toml_str: &str| toml::from_str::<NetworkMatch>(toml_str);
+        let subnet = |cidr: &str| match network(&format!("subnet = \"{cidr}\"")) {
+            Ok(NetworkMatch::Subnet(subnet)) => Ok(subnet),
+            Ok(_) => unreachable!(),
+            Err(e) => Err(e),
+        };
+
+        let home = subnet("192.168.1.0/24").unwrap();
+        assert!(home.contains("192.168.1.42".parse().unwrap()));
+        assert!(!home.contains("192.168.2.42".parse().unwrap()));
+        assert!(!home.contains("fd00::1".parse().unwrap()));
+        assert!(
+            subnet("0.0.0.0/0")
+                .unwrap()
+                .contains("8.8.8.8".parse().unwrap())
+        );
+        assert!(
+            subnet("fd00::/8")
+                .unwrap()
+                .contains("fd12::1".parse().unwrap())
+        );
+        for invalid in [
+            "192.168.1/24",
+            "192.168.1.0/33",
+            "192.168.1.0",
+            "fd00::/129",
+        ] {
+            assert!(subnet(invalid).is_err(), "{invalid} should be rejected");
+        }
+
+        let Ok(NetworkMatch::GatewayMac(mac)) = network("gateway_mac = \"A4:2B:B0:12:34:56\"")
+        else {
+            panic!("MAC should load");
+        };
+        assert!(mac.matches("a4:2b:b0:12:34:56"));
+        assert!(network("gateway_mac = \"a4:2b:b0:12:34\"").is_err());
+        assert!(network("gateway_mac = \"home router\"").is_err());
+    }
+
+    #[test]
+    fn stop_condition_drops_top_level_launch_gates() {
+        let app: AppConfig = toml::from_str(
+            r#"
+            name = "Steam"
+            command = "steam"
+            not_show_in = ["i3"]
+
+            [[conditions.all]]
+            type = "system_idle"
+            max_load = 2.0
+
+            [[conditions.all]]
+            type = "internet"
+
+            [[conditions.all]]
+            type = "uptime_at_least"
+            seconds = 120
+
+            [[conditions.all]]
+            type = "not"
+            condition = { type = "system_idle", max_load = 8.0 }
+            "#,
+        )
+        .unwrap();
+
+        let Condition::All { conditions } = app.stop_condition() else {
+            panic!("stop condition should be an `all` node");
+        };
+        assert_eq!(conditions.len(), 3);
+        assert!(matches!(conditions[0], Condition::Internet { .. }));
+        assert!(matches!(conditions[1], Condition::Not { .. }));
+        assert!(matches!(conditions[2], Condition::Session { .. }));
+    }
+
+    #[test]
+    fn invalid_cmdline_regex_fails_to_load() {
+        let condition = |cmdline: &str| {
+            toml::from_str::<Condition>(&format!(
+                "type = \"process_absent\"\ncmdline = {cmdline:?}"
+            ))
+        };
+        let Ok(Condition::ProcessAbsent {
+            process: ProcessMatch::Cmdline(pattern),
+        }) = condition("gamescope|steamapps/common")
+        else {
+            panic!("valid pattern should load");
+        };
+        assert!(pattern.is_match("/usr/bin/gamescope -f -- steam"));
+        assert!(!pattern.is_match("firefox"));
+        assert!(condition("steam(").is_err());
     }
 }
@@ -1,31 +1,212 @@
-use crate::app::{ConditionalLauncherApp, perform_launch_checks};
+use crate::app::{ConditionCache, ConditionalLauncherApp, perform_launch_checks};
+use crate::config::{AppConfig, Condition};
 use crate::os::get_os_operations;
+use crate::schedule;
+use crate::supervisor::Supervisor;
+use chrono::{Local, NaiveDateTime};
 use std::time::Duration;
+use sysinfo::System;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Long waits are slept in steps, so a suspend doesn't push the wake-up back.
+const MAX_SLEEP_STEP: Duration = Duration::from_secs(60);
+
+/// The earliest moment `app` could launch while only time holds it back: its
+/// login delay, the uptime it waits for, or a closed schedule. `None` when it
+/// waits for anything else, which can only be polled.
+fn time_blocked_until(
+    app: &AppConfig,
+    login: NaiveDateTime,
+    now: NaiveDateTime,
+    uptime: u64,
+) -> Option<NaiveDateTime> {
+    let mut deadlines: Vec<_> = app
+        .delay_after_login
+        .map(|delay| login + Duration::from_secs(delay))
+        .into_iter()
+        .collect();
+    for condition in &app.conditions.all {
+        if let Condition::UptimeAtLeast { seconds } = condition
+            && *seconds > uptime
+        {
+            deadlines.push(now + Duration::from_secs(seconds - uptime));
+        }
+    }
+    deadlines.extend(schedule::blocked_until(&app.conditions, now));
+    deadlines
+        .into_iter()
+        .max()
+        .filter(|deadline| *deadline > now)
+}
+
+/// Sleeps until the next deadline instead of probing every few seconds, as
+/// long as every pending app is waiting for one.
+fn next_wake(
+    managed_apps: &[AppConfig],
+    login: NaiveDateTime,
+    now: NaiveDateTime,
+    uptime: u64,
+) -> NaiveDateTime {
+    managed_apps
+        .iter()
+        .filter(|a| !a.launched)
+        .map(|a| time_blocked_until(a, login, now, uptime))
+        .collect::<Option<Vec<_>>>()
+        .and_then(|deadlines| deadlines.into_iter().min())
+        .unwrap_or(now + POLL_INTERVAL)
+}
+
+fn sleep_until(deadline: NaiveDateTime) {
+    while let Ok(remaining) = (deadline - Local::now().naive_local()).to_std() {
+        if remaining.is_zero() {
+            break;
+        }
+        std::thread::sleep(remaining.min(MAX_SLEEP_STEP));
+    }
+}
 
 pub fn run_hidden_process() {
     let os_ops = get_os_operations();
-    let mut managed_apps = ConditionalLauncherApp::load_config();
+    let config = match ConditionalLauncherApp::load_config() {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{error}");
+            os_ops.notify("Nothing launched", &error);
+            return;
+        }
+    };
+    let mut managed_apps = config.apps;
     let total_apps_to_launch = managed_apps.len();
 
     if total_apps_to_launch == 0 {
         return;
     }
 
+    let login = Local::now().naive_local();
     let mut launched_app_names: Vec<String> = Vec::new();
+    let mut supervisor = Supervisor::default();
+    let mut cache = ConditionCache::default();
 
     loop {
-        perform_launch_checks(os_ops.as_ref(), &mut managed_apps);
+        let since_login = (Local::now().naive_local() - login)
+            .to_std()
+            .unwrap_or_default();
+        cache.since_login = Some(since_login);
+        cache.refresh(&config.connectivity);
+        let children =
+            perform_launch_checks(os_ops.as_ref(), &cache, &mut managed_apps, since_login);
+
+        if config.supervisor.enabled {
+            for (name, child) in children {
+                supervisor.track(name, child);
+            }
+            supervisor.stop_unmet(os_ops.as_ref(), &cache, &managed_apps);
+            supervisor.restart_scheduled(os_ops.as_ref(), &managed_apps);
+            supervisor.watch(os_ops.as_ref(), &managed_apps);
+            supervisor.reap(os_ops.as_ref(), &cache, &mut managed_apps);
+        } else {
+            for app in managed_apps.iter().filter(|a| a.launched) {
+                if !launched_app_names.contains(&app.name) {
+                    launched_app_names.push(app.name.clone());
+                }
+            }
 
-        for app in managed_apps.iter().filter(|a| a.launched) {
-            if !launched_app_names.contains(&app.name) {
-                launched_app_names.push(app.name.clone());
+            if launched_app_names.len() >= total_apps_to_launch {
+                break;
             }
         }
 
-        if launched_app_names.len() >= total_apps_to_launch {
-            break;
+        let now = Local::now().naive_local();
+        let mut wake = next_wake(&managed_apps, login, now, System::uptime());
+        // Running apps are polled for exits, however long the others wait.
+        if supervisor.is_busy() {
+            wake = wake.min(now + POLL_INTERVAL);
         }
+        sleep_until(wake);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        // A Monday.
+        NaiveDate::from_ymd_opt(2026, 10, 12)
+            .and_then(|date| date.and_hms_opt(hour, minute, second))
+            .unwrap()
+    }
+
+    fn app(toml_str: &str) -> AppConfig {
+        let conditions = if toml_str.contains("conditions") {
+            ""
+        } else {
+            "\n[conditions]\nall = []"
+        };
+        toml::from_str(&format!(
+            "name = \"App\"\ncommand = \"app\"\n{toml_str}{conditions}"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn time_blocked_until_takes_the_latest_deadline() {
+        let login = at(9, 0, 0);
+        let now = at(9, 0, 10);
+
+        let delayed = app("delay_after_login = 30");
+        assert_eq!(
+            time_blocked_until(&delayed, login, now, 100),
+            Some(at(9, 0, 30))
+        );
+        assert_eq!(time_blocked_until(&delayed, login, at(9, 0, 30), 100), None);
+
+        let uptime = app("delay_after_login = 30\n\
+             [[conditions.all]]\ntype = \"uptime_at_least\"\nseconds = 120");
+        assert_eq!(
+            time_blocked_until(&uptime, login, now, 60),
+            Some(at(9, 1, 10))
+        );
+        assert_eq!(
+            time_blocked_until(&uptime, login, now, 150),
+            Some(at(9, 0, 30))
+        );
+
+        let scheduled =
+            app("[[conditions.all]]\ntype = \"schedule\"\nfrom = \"10:00\"\nto = \"18:00\"");
+        assert_eq!(
+            time_blocked_until(&scheduled, login, now, 100),
+            Some(at(10, 0, 0))
+        );
+        assert_eq!(
+            time_blocked_until(&scheduled, login, at(11, 0, 0), 100),
+            None
+        );
+
+        // Not held back by time at all.
+        assert_eq!(time_blocked_until(&app(""), login, now, 100), None);
+    }
+
+    #[test]
+    fn next_wake_polls_unless_every_pending_app_waits_for_time() {
+        let login = at(9, 0, 0);
+        let now = at(9, 0, 10);
+        let delayed = app("delay_after_login = 60");
+        let soon = app("delay_after_login = 30");
+        let internet = app("[[conditions.all]]\ntype = \"internet\"");
+        let mut launched = app("");
+        launched.launched = true;
 
-        std::thread::sleep(Duration::from_secs(5));
+        assert_eq!(
+            next_wake(&[delayed.clone(), soon.clone(), launched], login, now, 100),
+            at(9, 0, 30)
+        );
+        // The internet can only be polled for.
+        assert_eq!(
+            next_wake(&[delayed, internet], login, now, 100),
+            now + POLL_INTERVAL
+        );
+        assert_eq!(next_wake(&[], login, now, 100), now + POLL_INTERVAL);
     }
 }
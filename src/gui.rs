@@ -1,6 +1,6 @@
-use crate::app::{ConditionalLauncherApp, check_app_conditions};
-use crate::config::AppConfig;
-use crate::os::{OsOperations, PartitionInfo};
+use crate::app::{ConditionCache, ConditionalLauncherApp, check_app_conditions};
+use crate::config::{AppConfig, Condition, UsbDeviceRef};
+use crate::os::{NetworkState, OsOperations, PartitionInfo, PowerState, UsbDeviceInfo};
 use eframe::egui;
 #[cfg(target_os = "linux")]
 use freedesktop_icons as icons;
@@ -8,40 +8,40 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::time::SystemTime;
-use sysinfo::{Disks, ProcessRefreshKind, RefreshKind, System};
 
 pub struct GuiApp {
     pub app: ConditionalLauncherApp,
     #[cfg(target_os = "linux")]
     texture_cache: HashMap<String, egui::TextureHandle>,
     last_autostart_check: Option<SystemTime>,
-    sys: System,
     last_cache_update: SystemTime,
-    cached_internet_ok: bool,
-    cached_disks: Disks,
+    condition_cache: ConditionCache,
     cached_running_status: HashMap<String, bool>,
     editing_app_name: Option<String>,
     edit_buffer_command: String,
     edit_buffer_working_dir: String,
+    edit_buffer_delay: u64,
+    edit_buffer_stop_when_unmet: bool,
 }
 
 impl GuiApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>, apps: Vec<AppConfig>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>, apps: Vec<AppConfig>) -> Self {
         let app = ConditionalLauncherApp::new(apps);
+        let ctx = cc.egui_ctx.clone();
 
         Self {
             app,
             #[cfg(target_os = "linux")]
             texture_cache: HashMap::new(),
             last_autostart_check: None,
-            sys: System::new_all(),
             last_cache_update: SystemTime::UNIX_EPOCH,
-            cached_internet_ok: false,
-            cached_disks: Disks::new(),
+            condition_cache: ConditionCache::with_background_checks(move || ctx.request_repaint()),
             cached_running_status: HashMap::new(),
             editing_app_name: None,
             edit_buffer_command: String::new(),
             edit_buffer_working_dir: String::new(),
+            edit_buffer_delay: 0,
+            edit_buffer_stop_when_unmet: false,
         }
     }
 
@@ -58,9 +58,7 @@ impl GuiApp {
             }
         }
 
-        self.app
-            .apps
-            .sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        self.app.apps.sort_by_key(|a| a.name.to_lowercase());
     }
 }
 
@@ -74,60 +72,258 @@ fn load_icon<'a>(
         return texture_cache.get(icon_name);
     }
 
-    if let Some(path) = icons::lookup(icon_name).with_size(32).find() {
-        if let Ok(image_data) = fs::read(&path) {
-            let color_image = if path.extension().is_some_and(|e| e == "svg") {
-                let rtree = usvg::Tree::from_data(&image_data, &usvg::Options::default()).ok()?;
-                let svg_size = rtree.size();
-                let (width, height) = (32, 32);
-                let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height)?;
-                let sx = width as f32 / svg_size.width();
-                let sy = height as f32 / svg_size.height();
-                let transform = resvg::tiny_skia::Transform::from_scale(sx, sy);
-                resvg::render(&rtree, transform, &mut pixmap.as_mut());
-                Some(egui::ColorImage::from_rgba_unmultiplied(
-                    [pixmap.width() as usize, pixmap.height() as usize],
-                    pixmap.data(),
-                ))
-            } else {
-                image::load_from_memory(&image_data).ok().map(|image| {
-                    let image_rgba = image.to_rgba8();
-                    let size = [image.width() as usize, image.height() as usize];
-                    egui::ColorImage::from_rgba_unmultiplied(size, &image_rgba.into_raw())
-                })
-            };
+    if let Some(path) = icons::lookup(icon_name).with_size(32).find()
+        && let Ok(image_data) = fs::read(&path)
+    {
+        let color_image = if path.extension().is_some_and(|e| e == "svg") {
+            let rtree = usvg::Tree::from_data(&image_data, &usvg::Options::default()).ok()?;
+            let svg_size = rtree.size();
+            let (width, height) = (32, 32);
+            let mut pixmap = resvg::tiny_skia::Pixmap::new(width, height)?;
+            let sx = width as f32 / svg_size.width();
+            let sy = height as f32 / svg_size.height();
+            let transform = resvg::tiny_skia::Transform::from_scale(sx, sy);
+            resvg::render(&rtree, transform, &mut pixmap.as_mut());
+            Some(egui::ColorImage::from_rgba_unmultiplied(
+                [pixmap.width() as usize, pixmap.height() as usize],
+                pixmap.data(),
+            ))
+        } else {
+            image::load_from_memory(&image_data).ok().map(|image| {
+                let image_rgba = image.to_rgba8();
+                let size = [image.width() as usize, image.height() as usize];
+                egui::ColorImage::from_rgba_unmultiplied(size, &image_rgba.into_raw())
+            })
+        };
 
-            if let Some(color_image) = color_image {
-                let texture =
-                    ctx.load_texture(icon_name.to_string(), color_image, Default::default());
-                texture_cache.insert(icon_name.to_string(), texture);
-                return texture_cache.get(icon_name);
-            }
+        if let Some(color_image) = color_image {
+            let texture = ctx.load_texture(icon_name.to_string(), color_image, Default::default());
+            texture_cache.insert(icon_name.to_string(), texture);
+            return texture_cache.get(icon_name);
         }
     }
     None
 }
 
+fn partition_name(partition: &PartitionInfo) -> String {
+    partition
+        .label
+        .clone()
+        .or_else(|| partition.mount_point.clone())
+        .unwrap_or_else(|| partition.device.clone())
+}
+
+fn partition_picker_label(partition: &PartitionInfo) -> String {
+    let location = partition.mount_point.as_deref().unwrap_or("not mounted");
+    let mut details = vec![location, &partition.fs_type, &partition.size];
+    details.retain(|detail| !detail.is_empty());
+    if partition.removable {
+        details.push("removable");
+    }
+    if partition.network {
+        details.push("network");
+    }
+    format!("{} ({})", partition_name(partition), details.join(", "))
+}
+
+fn usb_device_label(device: &UsbDeviceInfo) -> String {
+    let name = [device.manufacturer.as_deref(), device.product.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if name.is_empty() {
+        device.id()
+    } else {
+        format!("{name} ({})", device.id())
+    }
+}
+
+fn power_summary(power: &PowerState) -> String {
+    let mut summary = match (power.on_ac, power.battery_percent) {
+        (true, None) => "AC".to_string(),
+        (true, Some(percent)) => format!("AC, {percent}%"),
+        (false, Some(percent)) => format!("Battery {percent}%"),
+        (false, None) => "Battery".to_string(),
+    };
+    if power.power_saver {
+        summary.push_str(", power saver");
+    }
+    summary
+}
+
+fn draw_path_controls(ui: &mut egui::Ui, app: &mut AppConfig, path_ok: bool) {
+    let (current_path, mut require) = app
+        .conditions
+        .path_exists()
+        .map(|(path, require)| (path.to_string(), require))
+        .unwrap_or_default();
+
+    ui.horizontal(|ui| {
+        ui.label("Path:")
+            .on_hover_text("If set, this app will only launch if this file or directory exists.");
+
+        // Edits are applied when the field loses focus, so the app isn't
+        // managed and unmanaged on every keystroke.
+        let edit_id = egui::Id::new(("path_exists", &app.name));
+        let focused = ui.memory(|m| m.has_focus(edit_id));
+        let mut buffer = if focused {
+            ui.data(|d| d.get_temp::<String>(edit_id))
+                .unwrap_or_else(|| current_path.clone())
+        } else {
+            current_path.clone()
+        };
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut buffer)
+                .id(edit_id)
+                .hint_text("None")
+                .desired_width(200.0),
+        );
+        let mut new_path = None;
+        if response.lost_focus() {
+            new_path = Some(buffer.trim().to_string());
+        }
+        ui.data_mut(|d| d.insert_temp(edit_id, buffer));
+
+        if ui.button("File…").on_hover_text("Pick a file.").clicked()
+            && let Some(path) = rfd::FileDialog::new().pick_file()
+        {
+            new_path = Some(path.to_string_lossy().to_string());
+        }
+        if ui
+            .button("Folder…")
+            .on_hover_text("Pick a directory.")
+            .clicked()
+            && let Some(path) = rfd::FileDialog::new().pick_folder()
+        {
+            require.is_dir = true;
+            new_path = Some(path.to_string_lossy().to_string());
+        }
+
+        if !current_path.is_empty() {
+            ui.checkbox(&mut require.is_dir, "Dir")
+                .on_hover_text("The path must be a directory.");
+            ui.checkbox(&mut require.non_empty, "Non-empty")
+                .on_hover_text("The directory must have entries, or the file must not be empty.");
+            ui.checkbox(&mut require.writable, "Writable")
+                .on_hover_text("The path must be writable, e.g. not on a read-only mount.");
+
+            let text = if path_ok { "✅" } else { "❌" };
+            ui.label(text)
+                .on_hover_text("Current status of the selected path.");
+        }
+
+        let path = new_path.unwrap_or(current_path);
+        let path_exists = (!path.is_empty()).then_some((path, require));
+        if path_exists.as_ref().map(|(p, r)| (p.as_str(), *r)) != app.conditions.path_exists() {
+            app.conditions.set_path_exists(path_exists);
+        }
+    });
+}
+
+fn draw_usb_controls(
+    ui: &mut egui::Ui,
+    app: &mut AppConfig,
+    usb_devices: &[UsbDeviceInfo],
+    usb_ok: bool,
+) {
+    ui.horizontal(|ui| {
+        ui.label("USB:").on_hover_text(
+            "If set, this app will only launch while this USB device is plugged in.",
+        );
+
+        let mut device = app.conditions.usb_device().cloned();
+        let selected_info = device
+            .as_ref()
+            .and_then(|selected| usb_devices.iter().find(|d| d.matches(selected)));
+        let selected_text = match (&device, selected_info) {
+            (None, _) => "None".to_string(),
+            (Some(_), Some(info)) => usb_device_label(info),
+            (Some(selected), None) => format!("{selected} (missing)"),
+        };
+
+        egui::ComboBox::from_id_salt(("usb_device", &app.name))
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut device, None, "None");
+                if let Some(selected) = app.conditions.usb_device()
+                    && selected_info.is_none()
+                {
+                    ui.selectable_value(
+                        &mut device,
+                        Some(selected.clone()),
+                        format!("{selected} (missing)"),
+                    )
+                    .on_hover_text("The selected device is not plugged in right now.");
+                }
+                for d in usb_devices {
+                    let is_selected = device.as_ref().is_some_and(|s| d.matches(s));
+                    if ui
+                        .selectable_label(is_selected, usb_device_label(d))
+                        .clicked()
+                    {
+                        device = Some(UsbDeviceRef::Id(d.id()));
+                    }
+                }
+            });
+        if device.as_ref() != app.conditions.usb_device() {
+            app.conditions.set_usb_device(device);
+        }
+
+        if app.conditions.usb_device().is_some() {
+            let text = if usb_ok { "✅" } else { "❌" };
+            ui.label(text)
+                .on_hover_text("Current status of the selected device.");
+        }
+    });
+}
+
 fn draw_condition_controls(
     ui: &mut egui::Ui,
     app: &mut AppConfig,
     available_partitions: &[PartitionInfo],
     os_ops: &dyn OsOperations,
-    cached_internet_ok: bool,
-    cached_disks: &Disks,
+    condition_cache: &ConditionCache,
 ) {
+    let status = check_app_conditions(os_ops, app, condition_cache);
+    let leaf_status = |matches: fn(&Condition) -> bool| {
+        app.conditions
+            .all
+            .iter()
+            .position(matches)
+            .and_then(|i| status.children.get(i))
+    };
+    let internet_ok =
+        leaf_status(|c| matches!(c, Condition::Internet { .. })).is_some_and(|s| s.ok);
+    let partition_status = leaf_status(|c| matches!(c, Condition::PartitionMounted { .. }));
+    let partition_ok = partition_status.is_some_and(|s| s.ok);
+    let ac_power_ok = leaf_status(|c| *c == Condition::OnAcPower).is_some_and(|s| s.ok);
+    let path_ok = leaf_status(|c| matches!(c, Condition::PathExists { .. })).is_some_and(|s| s.ok);
+    let usb_ok = leaf_status(|c| matches!(c, Condition::UsbDevice { .. })).is_some_and(|s| s.ok);
+
     ui.horizontal(|ui| {
-        ui.checkbox(&mut app.conditions.internet, "Internet")
+        let mut internet = app.conditions.internet();
+        if ui
+            .checkbox(&mut internet, "Internet")
             .on_hover_text(
                 "If checked, this app will only launch if there is an active internet connection.",
-            );
-
-        let status = check_app_conditions(os_ops, app, cached_internet_ok, cached_disks);
+            )
+            .changed()
+        {
+            app.conditions.set_internet(internet);
+        }
 
-        if app.conditions.internet {
-            let text = if status.internet_ok { "✅" } else { "❌" };
-            ui.label(text)
-                .on_hover_text("Current internet connection status.");
+        if app.conditions.internet() {
+            if condition_cache.network(os_ops) == NetworkState::CaptivePortal {
+                let text = if internet_ok { "✅ ⚠" } else { "❌ ⚠" };
+                ui.label(text).on_hover_text(
+                    "Behind a captive portal: log in through the browser to get online.",
+                );
+            } else {
+                let text = if internet_ok { "✅" } else { "❌" };
+                ui.label(text)
+                    .on_hover_text("Current internet connection status.");
+            }
         }
 
         ui.separator();
@@ -135,37 +331,141 @@ fn draw_condition_controls(
         ui.label("Partition:").on_hover_text(
             "If a partition is selected, this app will only launch if that partition is mounted.",
         );
-        let selected_text = app
-            .conditions
-            .partition_mounted
-            .as_deref()
-            .unwrap_or("None");
+        let mut partition = app.conditions.partition_mounted().cloned();
+        let selected_info = partition
+            .as_ref()
+            .and_then(|selected| available_partitions.iter().find(|p| p.matches(selected)));
+        let selected_text = match (&partition, selected_info) {
+            (None, _) => "None".to_string(),
+            (Some(_), Some(info)) => partition_name(info),
+            (Some(selected), None) => format!("{selected} (missing)"),
+        };
 
         egui::ComboBox::from_id_salt(&app.name)
             .selected_text(selected_text)
             .show_ui(ui, |ui| {
-                ui.selectable_value(&mut app.conditions.partition_mounted, None, "None");
+                ui.selectable_value(&mut partition, None, "None");
+                if let Some(selected) = app.conditions.partition_mounted()
+                    && selected_info.is_none()
+                {
+                    ui.selectable_value(
+                        &mut partition,
+                        Some(selected.clone()),
+                        format!("{selected} (missing)"),
+                    )
+                    .on_hover_text("The selected partition is not connected right now.");
+                }
                 for p in available_partitions.iter() {
-                    let display_label = &p.mount_point;
-                    let details = if p.fs_type.is_empty() {
-                        format!("({})", p.size)
-                    } else {
-                        format!("({}, {})", p.fs_type, p.size)
+                    let Some(partition_ref) = p.partition_ref() else {
+                        continue;
                     };
-                    ui.selectable_value(
-                        &mut app.conditions.partition_mounted,
-                        Some(p.mount_point.clone()),
-                        format!("{display_label} {details}"),
-                    );
+                    let is_selected = partition.as_ref().is_some_and(|s| p.matches(s));
+                    if ui
+                        .selectable_label(is_selected, partition_picker_label(p))
+                        .clicked()
+                    {
+                        partition = Some(partition_ref);
+                    }
                 }
             });
+        if partition.as_ref() != app.conditions.partition_mounted() {
+            app.conditions.set_partition_mounted(partition);
+        }
 
-        if app.conditions.partition_mounted.is_some() {
-            let text = if status.partition_ok { "✅" } else { "❌" };
-            ui.label(text)
-                .on_hover_text("Current status of the selected partition.");
+        if app.conditions.partition_mounted().is_some() {
+            let mut require = app.conditions.partition_requirements();
+            ui.checkbox(&mut require.read_write, "RW")
+                .on_hover_text("The partition must not be mounted read-only.");
+            ui.checkbox(&mut require.responsive, "Responsive").on_hover_text(
+                "The mount must answer within a few seconds, so a dead network share doesn't count.",
+            );
+            let mut min_free_gb = require.min_free_gb.unwrap_or(0);
+            ui.add(egui::DragValue::new(&mut min_free_gb).suffix(" GB free"))
+                .on_hover_text("Minimum free space on the partition, 0 for no minimum.");
+            require.min_free_gb = (min_free_gb > 0).then_some(min_free_gb);
+            if require != app.conditions.partition_requirements() {
+                app.conditions.set_partition_requirements(require);
+            }
+
+            let text = if partition_ok { "✅" } else { "❌" };
+            let hover = match (
+                partition_status.and_then(|s| s.detail.as_deref()),
+                partition_status.and_then(|s| s.mount_point()),
+            ) {
+                (Some(detail), _) => format!("{detail}."),
+                (None, Some(mount_point)) => format!("Mounted at {}.", mount_point.display()),
+                (None, None) => "Current status of the selected partition.".to_string(),
+            };
+            ui.label(text).on_hover_text(hover);
+        }
+
+        ui.separator();
+
+        let mut on_ac_power = app.conditions.on_ac_power();
+        if ui
+            .checkbox(&mut on_ac_power, "AC power")
+            .on_hover_text("If checked, this app will only launch while plugged in.")
+            .changed()
+        {
+            app.conditions.set_on_ac_power(on_ac_power);
+        }
+
+        if app.conditions.on_ac_power() {
+            let mark = if ac_power_ok { "✅" } else { "❌" };
+            ui.label(format!("{mark} {}", power_summary(condition_cache.power(os_ops))))
+                .on_hover_text("Current power source.");
+        }
+
+        if !app.conditions.is_empty() {
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let text = match (status.ok, status.pending) {
+                    (true, _) => "Ready",
+                    (false, true) => "Checking…",
+                    (false, false) => "Blocked",
+                };
+                ui.label(egui::RichText::new(text).small())
+                    .on_hover_text(status.to_string().trim_end());
+            });
         }
     });
+
+    draw_path_controls(ui, app, path_ok);
+    draw_usb_controls(ui, app, condition_cache.usb_devices(os_ops), usb_ok);
+
+    // Conditions without a dedicated editor are set in the config file; show
+    // their live status so it's clear what is holding the app back.
+    let other_conditions: Vec<_> = app
+        .conditions
+        .all
+        .iter()
+        .zip(&status.children)
+        .filter(|(c, _)| {
+            !matches!(
+                c,
+                Condition::Internet { .. }
+                    | Condition::PartitionMounted { .. }
+                    | Condition::OnAcPower
+                    | Condition::PathExists { .. }
+                    | Condition::UsbDevice { .. }
+            )
+        })
+        .collect();
+    // The desktop entry's OnlyShowIn/NotShowIn, appended by launch_condition.
+    let desktop_restriction = status.children.get(app.conditions.all.len());
+    if !other_conditions.is_empty() || desktop_restriction.is_some() {
+        ui.horizontal_wrapped(|ui| {
+            for (condition, child) in other_conditions {
+                let response = ui.label(format!("{condition} {}", child.mark()));
+                if !child.children.is_empty() || child.detail.is_some() {
+                    response.on_hover_text(child.to_string().trim_end());
+                }
+            }
+            if let Some(child) = desktop_restriction {
+                ui.label(format!("{} {}", child.label, child.mark()))
+                    .on_hover_text("From the desktop entry's OnlyShowIn/NotShowIn.");
+            }
+        });
+    }
 }
 
 impl eframe::App for GuiApp {
@@ -180,12 +480,8 @@ impl eframe::App for GuiApp {
             .as_secs()
             >= 5
         {
-            self.cached_internet_ok = self.app.os_ops.check_internet_connection();
-            self.cached_disks.refresh(true);
-
-            self.sys.refresh_specifics(
-                RefreshKind::nothing().with_processes(ProcessRefreshKind::everything()),
-            );
+            self.condition_cache.refresh(&self.app.connectivity);
+            self.app.available_partitions = self.app.os_ops.get_partitions();
 
             let autostart_path = dirs::config_dir().unwrap().join("autostart");
             if let Ok(metadata) = fs::metadata(&autostart_path)
@@ -198,13 +494,17 @@ impl eframe::App for GuiApp {
 
             self.cached_running_status.clear();
             for app in self.app.apps.iter() {
-                let is_running = self.app.os_ops.is_app_running(app, &self.sys);
+                let is_running = self
+                    .app
+                    .os_ops
+                    .is_app_running(app, &self.condition_cache.system());
                 self.cached_running_status
                     .insert(app.name.clone(), is_running);
             }
 
             self.last_cache_update = SystemTime::now();
         }
+        self.condition_cache.collect_background_results();
 
         let panel_frame = egui::Frame {
             inner_margin: egui::Margin::symmetric(10, 10),
@@ -214,7 +514,15 @@ impl eframe::App for GuiApp {
         egui::CentralPanel::default()
             .frame(panel_frame)
             .show(ctx, |ui| {
+                if let Some(error) = &self.app.config_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                    ui.label("Fix the file and restart. Nothing is saved until then.");
+                    ui.separator();
+                }
                 egui::ScrollArea::vertical().show(ui, |ui| {
+                    if self.app.config_error.is_some() {
+                        ui.disable();
+                    }
                     let mut needs_save = false;
                     let mut app_to_manage = None;
                     let mut app_to_unmanage = None;
@@ -232,16 +540,15 @@ impl eframe::App for GuiApp {
                                 #[cfg(target_os = "linux")]
                                 {
                                     let mut icon_shown = false;
-                                    if let Some(icon_name) = &app.icon {
-                                        if let Some(texture) =
+                                    if let Some(icon_name) = &app.icon
+                                        && let Some(texture) =
                                             load_icon(&mut self.texture_cache, ctx, icon_name)
-                                        {
-                                            ui.add(
-                                                egui::Image::new(texture)
-                                                    .max_size(egui::vec2(20.0, 20.0)),
-                                            );
-                                            icon_shown = true;
-                                        }
+                                    {
+                                        ui.add(
+                                            egui::Image::new(texture)
+                                                .max_size(egui::vec2(20.0, 20.0)),
+                                        );
+                                        icon_shown = true;
                                     }
                                     if !icon_shown {
                                         let fallback = app.name.chars().next().unwrap_or('?');
@@ -284,6 +591,23 @@ impl eframe::App for GuiApp {
                                             .desired_width(f32::INFINITY),
                                         );
                                     });
+                                    ui.horizontal(|ui| {
+                                        ui.label("Delay after login:").on_hover_text(
+                                            "Hold the app back for this long after login, even once its conditions are met.",
+                                        );
+                                        ui.add(
+                                            egui::DragValue::new(&mut self.edit_buffer_delay)
+                                                .range(0..=3600)
+                                                .suffix(" s"),
+                                        );
+                                    });
+                                    ui.checkbox(
+                                        &mut self.edit_buffer_stop_when_unmet,
+                                        "Stop when conditions fail",
+                                    )
+                                    .on_hover_text(
+                                        "In supervisor mode, quit the app once its conditions no longer hold.",
+                                    );
                                 });
 
                                 ui.horizontal(|ui| {
@@ -295,6 +619,9 @@ impl eframe::App for GuiApp {
                                         } else {
                                             Some(PathBuf::from(path_str))
                                         };
+                                        app.delay_after_login = (self.edit_buffer_delay > 0)
+                                            .then_some(self.edit_buffer_delay);
+                                        app.stop_when_unmet = self.edit_buffer_stop_when_unmet;
                                         self.editing_app_name = None;
                                         needs_save = true;
                                     }
@@ -318,7 +645,15 @@ impl eframe::App for GuiApp {
                                                     .on_hover_text("Launch this application now.")
                                                     .clicked()
                                             {
-                                                self.app.os_ops.launch_app(app);
+                                                let status = check_app_conditions(
+                                                    self.app.os_ops.as_ref(),
+                                                    app,
+                                                    &self.condition_cache,
+                                                );
+                                                let _ = self
+                                                    .app
+                                                    .os_ops
+                                                    .launch_app(app, status.mount_point());
                                             }
                                             if app.is_managed && ui.button("Edit").clicked() {
                                                 self.editing_app_name = Some(app.name.clone());
@@ -328,6 +663,10 @@ impl eframe::App for GuiApp {
                                                     .as_ref()
                                                     .map(|p| p.to_string_lossy().to_string())
                                                     .unwrap_or_default();
+                                                self.edit_buffer_delay =
+                                                    app.delay_after_login.unwrap_or_default();
+                                                self.edit_buffer_stop_when_unmet =
+                                                    app.stop_when_unmet;
                                             }
                                         },
                                     );
@@ -345,6 +684,16 @@ impl eframe::App for GuiApp {
                                         .monospace(),
                                     );
                                 }
+
+                                if let Some(delay) = app.delay_after_login {
+                                    ui.label(
+                                        egui::RichText::new(format!(
+                                            "Delay after login: {delay} s"
+                                        ))
+                                        .small()
+                                        .monospace(),
+                                    );
+                                }
                             }
 
                             ui.separator();
@@ -354,14 +703,12 @@ impl eframe::App for GuiApp {
                                 app,
                                 &self.app.available_partitions,
                                 self.app.os_ops.as_ref(),
-                                self.cached_internet_ok,
-                                &self.cached_disks,
+                                &self.condition_cache,
                             );
                             let conditions_changed = app.conditions != old_conditions;
 
                             if conditions_changed {
-                                let should_be_managed = app.conditions.internet
-                                    || app.conditions.partition_mounted.is_some();
+                                let should_be_managed = !app.conditions.is_empty();
 
                                 if app.is_managed {
                                     if should_be_managed {
@@ -5,6 +5,8 @@ mod config;
 mod daemon;
 mod gui;
 mod os;
+mod schedule;
+mod supervisor;
 
 use eframe::egui;
 
@@ -18,7 +20,7 @@ fn main() -> Result<(), eframe::Error> {
         let os_ops = os::get_os_operations();
         let apps = app::load_all_apps(os_ops.as_ref());
 
-        const HEIGHT_PER_APP: f32 = 95.0;
+        const HEIGHT_PER_APP: f32 = 140.0;
         const PADDING: f32 = 30.0;
         const MIN_HEIGHT: f32 = 150.0;
         const MAX_HEIGHT: f32 = 700.0;
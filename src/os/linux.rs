@@ -1,21 +1,170 @@
-use super::{OsOperations, PartitionInfo};
-use crate::config::AppConfig;
+use super::{
+    CommandResult, DisplayInfo, FsSpace, LocalNetwork, MountedFs, NetworkState, OsOperations,
+    PartitionInfo, PowerState, ProcessUsage, SystemLoad, UsbDeviceInfo,
+};
+use crate::config::{
+    AppConfig, ConnectivityConfig, PartitionRef, PathRequirements, Probe, ProcessMatch,
+};
 use freedesktop_desktop_entry::DesktopEntry;
 use reqwest;
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::ffi::CString;
 use std::fs;
+use std::io::{ErrorKind, Read};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
-use sysinfo::{Disks, System};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use sysinfo::{Networks, Pid, System};
 
-pub struct LinuxOperations;
+/// `/sys`, `/proc` and power-profiles-daemon's state are read below a root
+/// that tests swap for a fake tree. Device nodes and udev's database always
+/// come from the real system.
+pub struct LinuxOperations {
+    sysfs_root: PathBuf,
+    procfs_root: PathBuf,
+    power_profiles_state: PathBuf,
+    /// Paths whose check is still running; see `with_timeout`.
+    stuck_paths: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl Default for LinuxOperations {
+    fn default() -> Self {
+        Self::with_root("/")
+    }
+}
+
+/// Port knocked on when a host is probed without an explicit port. Whether it
+/// accepts or refuses, an answer means the host is up.
+const HOST_PROBE_PORT: u16 = 80;
+
+const DISK_BY_DIR: &str = "/dev/disk";
+const UDEV_DATA_DIR: &str = "/run/udev/data";
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(20);
+const NOTIFY_APP_NAME: &str = "Conditional Launcher";
+/// Relative to the root `LinuxOperations` reads from.
+const POWER_PROFILES_STATE: &str = "var/lib/power-profiles-daemon/state.ini";
+
+/// Exported to launched commands when a partition condition resolved to a
+/// mount point, so they can use `"$CONDITIONAL_LAUNCHER_MOUNT_POINT"`.
+const MOUNT_POINT_ENV: &str = "CONDITIONAL_LAUNCHER_MOUNT_POINT";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ProbeOutcome {
+    Pass,
+    CaptivePortal,
+    Fail,
+}
 
 impl LinuxOperations {
+    /// Reads `sys`, `proc` and power-profiles-daemon's state below `root`.
+    pub fn with_root(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        Self {
+            sysfs_root: root.join("sys"),
+            procfs_root: root.join("proc"),
+            power_profiles_state: root.join(POWER_PROFILES_STATE),
+            stuck_paths: Arc::default(),
+        }
+    }
+
+    fn read_sysfs(dir: &Path, attribute: &str) -> Option<String> {
+        fs::read_to_string(dir.join(attribute))
+            .ok()
+            .map(|s| s.trim().to_string())
+    }
+
+    /// Gateways of the IPv4 default routes.
+    fn default_gateways(&self) -> Vec<Ipv4Addr> {
+        const RTF_GATEWAY: u32 = 0x2;
+        let routes = fs::read_to_string(self.procfs_root.join("net/route")).unwrap_or_default();
+        routes
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let fields: Vec<_> = line.split_whitespace().collect();
+                let hex = |i: usize| u32::from_str_radix(fields.get(i)?, 16).ok();
+                let (destination, gateway, flags) = (hex(1)?, hex(2)?, hex(3)?);
+                // The kernel prints addresses in network byte order.
+                (destination == 0 && flags & RTF_GATEWAY != 0)
+                    .then(|| Ipv4Addr::from(gateway.to_ne_bytes()))
+            })
+            .collect()
+    }
+
+    /// Resolved entries of the ARP table, IP to lowercase MAC.
+    fn arp_table(&self) -> HashMap<Ipv4Addr, String> {
+        let arp = fs::read_to_string(self.procfs_root.join("net/arp")).unwrap_or_default();
+        arp.lines()
+            .skip(1)
+            .filter_map(|line| {
+                let fields: Vec<_> = line.split_whitespace().collect();
+                let mac = fields.get(3)?.to_lowercase();
+                (mac != "00:00:00:00:00:00").then_some((fields.first()?.parse().ok()?, mac))
+            })
+            .collect()
+    }
+
+    /// The monitor name descriptor (tag 0xFC) of an EDID base block.
+    fn edid_monitor_name(edid: &[u8]) -> Option<String> {
+        (54..126).step_by(18).find_map(|offset| {
+            let descriptor = edid.get(offset..offset + 18)?;
+            if descriptor[..3] != [0, 0, 0] || descriptor[3] != 0xFC {
+                return None;
+            }
+            let text = &descriptor[5..];
+            let end = text.iter().position(|&b| b == b'\n').unwrap_or(text.len());
+            let name = String::from_utf8_lossy(&text[..end]).trim().to_string();
+            (!name.is_empty()).then_some(name)
+        })
+    }
+
+    /// The "some avg10" value of a `/proc/pressure` resource.
+    fn read_pressure(&self, resource: &str) -> Option<f64> {
+        fs::read_to_string(self.procfs_root.join("pressure").join(resource))
+            .ok()?
+            .lines()
+            .find_map(|line| line.strip_prefix("some "))?
+            .split(' ')
+            .find_map(|field| field.strip_prefix("avg10="))?
+            .parse()
+            .ok()
+    }
+
+    /// Either power-profiles-daemon's saved profile or the ACPI platform
+    /// profile says so.
+    fn is_power_saver(&self) -> bool {
+        let ppd_profile = fs::read_to_string(&self.power_profiles_state)
+            .ok()
+            .and_then(|state| {
+                state
+                    .lines()
+                    .find_map(|l| l.strip_prefix("Profile=").map(|p| p.trim().to_string()))
+            });
+        if let Some(profile) = ppd_profile {
+            return profile == "power-saver";
+        }
+        Self::read_sysfs(&self.sysfs_root.join("firmware/acpi"), "platform_profile")
+            .is_some_and(|profile| profile == "low-power" || profile == "quiet")
+    }
+
     fn parse_desktop_file(path: PathBuf) -> Option<AppConfig> {
         let entry = DesktopEntry::from_path(&path, Some(&[] as &[&str])).ok()?;
         let name = entry.name(&[] as &[&str]).map(|s| s.to_string())?;
         let command = entry.exec().map(|s| s.to_string())?;
+        let show_in = |desktops: Option<Vec<&str>>| {
+            desktops
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|d| !d.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
 
         Some(AppConfig {
             name,
@@ -24,6 +173,15 @@ impl LinuxOperations {
             working_dir: entry.path().map(|s| PathBuf::from(s.to_string())),
             original_path: Some(path),
             conditions: Default::default(),
+            delay_after_login: None,
+            stop_when_unmet: false,
+            stop_after_secs: None,
+            stop_grace_secs: None,
+            restart: Default::default(),
+            scheduled_restart: None,
+            watchdog: None,
+            only_show_in: show_in(entry.only_show_in()),
+            not_show_in: show_in(entry.not_show_in()),
             launched: false,
             is_managed: false,
         })
@@ -65,6 +223,411 @@ impl LinuxOperations {
         fs::write(original_path, placeholder_content)
     }
 
+    fn run_probe(probe: &Probe) -> ProbeOutcome {
+        let outcome = |ok| {
+            if ok {
+                ProbeOutcome::Pass
+            } else {
+                ProbeOutcome::Fail
+            }
+        };
+        match probe {
+            Probe::Http {
+                url,
+                expected_status,
+                expected_body,
+                timeout_ms,
+            } => Self::probe_http(
+                url,
+                *expected_status,
+                expected_body.as_deref(),
+                Duration::from_millis(*timeout_ms),
+            ),
+            Probe::Dns {
+                host,
+                server,
+                timeout_ms,
+            } => {
+                let timeout = Duration::from_millis(*timeout_ms);
+                outcome(match server {
+                    Some(server) => Self::probe_dns_server(host, server, timeout),
+                    None => Self::probe_dns_system(host, timeout),
+                })
+            }
+            Probe::Tcp {
+                host,
+                port,
+                timeout_ms,
+            } => outcome(Self::probe_tcp(
+                host,
+                *port,
+                Duration::from_millis(*timeout_ms),
+            )),
+        }
+    }
+
+    fn probe_http(
+        url: &str,
+        expected_status: Option<u16>,
+        expected_body: Option<&str>,
+        timeout: Duration,
+    ) -> ProbeOutcome {
+        let Ok(client) = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+        else {
+            return ProbeOutcome::Fail;
+        };
+        let Ok(response) = client.get(url).send() else {
+            return ProbeOutcome::Fail;
+        };
+
+        let status = response.status();
+        let status_ok = match expected_status {
+            Some(expected) => status.as_u16() == expected,
+            None => status.is_success(),
+        };
+        if !status_ok {
+            // Only a 204 endpoint or a known body tells a portal's redirect or
+            // login page apart from a URL that just answers differently.
+            let detects_portal = expected_status == Some(204) || expected_body.is_some();
+            return if detects_portal && (status.is_success() || status.is_redirection()) {
+                ProbeOutcome::CaptivePortal
+            } else {
+                ProbeOutcome::Fail
+            };
+        }
+        match expected_body {
+            Some(expected) => match response.text() {
+                Ok(body) if body.contains(expected) => ProbeOutcome::Pass,
+                Ok(_) => ProbeOutcome::CaptivePortal,
+                Err(_) => ProbeOutcome::Fail,
+            },
+            None => ProbeOutcome::Pass,
+        }
+    }
+
+    /// Resolves through libc in a helper thread, since `getaddrinfo` has no
+    /// timeout of its own.
+    fn resolve_with_timeout(host: &str, port: u16, timeout: Duration) -> Vec<SocketAddr> {
+        let (tx, rx) = mpsc::channel();
+        let host = host.to_string();
+        thread::spawn(move || {
+            let addrs: Vec<_> = (host.as_str(), port)
+                .to_socket_addrs()
+                .map(|a| a.collect())
+                .unwrap_or_default();
+            let _ = tx.send(addrs);
+        });
+        rx.recv_timeout(timeout).unwrap_or_default()
+    }
+
+    fn probe_dns_system(host: &str, timeout: Duration) -> bool {
+        !Self::resolve_with_timeout(host, 0, timeout).is_empty()
+    }
+
+    /// Sends a single A query to `server` and passes on a non-empty answer.
+    fn probe_dns_server(host: &str, server: &str, timeout: Duration) -> bool {
+        let server = match server.parse::<SocketAddr>() {
+            Ok(addr) => addr,
+            Err(_) => match server.parse::<IpAddr>() {
+                Ok(ip) => SocketAddr::new(ip, 53),
+                Err(_) => return false,
+            },
+        };
+        let bind_addr = if server.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let Ok(socket) = UdpSocket::bind(bind_addr) else {
+            return false;
+        };
+        if socket.set_read_timeout(Some(timeout)).is_err() {
+            return false;
+        }
+
+        let id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos() as u16)
+            .unwrap_or_default();
+        let mut query = Vec::with_capacity(32 + host.len());
+        query.extend_from_slice(&id.to_be_bytes());
+        query.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+        for label in host.trim_end_matches('.').split('.') {
+            if label.is_empty() || label.len() > 63 {
+                return false;
+            }
+            query.push(label.len() as u8);
+            query.extend_from_slice(label.as_bytes());
+        }
+        query.extend_from_slice(&[0, 0, 1, 0, 1]);
+
+        if socket.send_to(&query, server).is_err() {
+            return false;
+        }
+        let mut buf = [0u8; 512];
+        match socket.recv_from(&mut buf) {
+            Ok((len, _)) if len >= 12 => {
+                let same_id = buf[0..2] == id.to_be_bytes();
+                let is_response = buf[2] & 0x80 != 0;
+                let no_error = buf[3] & 0x0f == 0;
+                let answers = u16::from_be_bytes([buf[6], buf[7]]);
+                same_id && is_response && no_error && answers > 0
+            }
+            _ => false,
+        }
+    }
+
+    fn probe_tcp(host: &str, port: u16, timeout: Duration) -> bool {
+        Self::tcp_connect(host, port, timeout, false)
+    }
+
+    fn tcp_connect(host: &str, port: u16, timeout: Duration, accept_refused: bool) -> bool {
+        Self::resolve_with_timeout(host, port, timeout)
+            .iter()
+            .any(|addr| match TcpStream::connect_timeout(addr, timeout) {
+                Ok(_) => true,
+                Err(e) => accept_refused && e.kind() == ErrorKind::ConnectionRefused,
+            })
+    }
+
+    /// Encodes an identifier the way udev names its `/dev/disk/by-*` links.
+    fn udev_encode(id: &str) -> String {
+        let mut encoded = String::with_capacity(id.len());
+        for c in id.chars() {
+            if c.is_ascii_alphanumeric() || !c.is_ascii() || "#+-.:=@_".contains(c) {
+                encoded.push(c);
+            } else {
+                encoded.push_str(&format!("\\x{:02x}", c as u8));
+            }
+        }
+        encoded
+    }
+
+    fn udev_decode(name: &str) -> String {
+        let mut decoded = Vec::with_capacity(name.len());
+        let bytes = name.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'\\'
+                && bytes.get(i + 1) == Some(&b'x')
+                && let Some(byte) = name
+                    .get(i + 2..i + 4)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                decoded.push(byte);
+                i += 4;
+            } else {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+        String::from_utf8_lossy(&decoded).into_owned()
+    }
+
+    fn device_for(partition: &PartitionRef) -> Option<PathBuf> {
+        let (dir, id) = match partition {
+            PartitionRef::Path(_) => return None,
+            PartitionRef::Uuid(id) => ("by-uuid", id),
+            PartitionRef::Partuuid(id) => ("by-partuuid", id),
+            PartitionRef::Label(id) => ("by-label", id),
+        };
+        fs::canonicalize(Path::new(DISK_BY_DIR).join(dir).join(Self::udev_encode(id))).ok()
+    }
+
+    /// Maps every device in `/dev/disk/<dir>` to its decoded identifier.
+    fn device_ids(dir: &str) -> HashMap<PathBuf, String> {
+        let Ok(entries) = fs::read_dir(Path::new(DISK_BY_DIR).join(dir)) else {
+            return HashMap::new();
+        };
+        entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let device = fs::canonicalize(entry.path()).ok()?;
+                let id = Self::udev_decode(entry.file_name().to_str()?);
+                Some((device, id))
+            })
+            .collect()
+    }
+
+    /// Undoes the octal escaping of spaces and friends in mountinfo paths.
+    fn unescape_mount_path(path: &str) -> String {
+        let mut unescaped = Vec::with_capacity(path.len());
+        let bytes = path.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'\\'
+                && let Some(byte) = path
+                    .get(i + 1..i + 4)
+                    .and_then(|oct| u8::from_str_radix(oct, 8).ok())
+            {
+                unescaped.push(byte);
+                i += 4;
+            } else {
+                unescaped.push(bytes[i]);
+                i += 1;
+            }
+        }
+        String::from_utf8_lossy(&unescaped).into_owned()
+    }
+
+    /// Every mount, from mountinfo. Read-only if either the mount or the
+    /// filesystem says so.
+    fn parse_mountinfo(mountinfo: &str) -> Vec<MountedFs> {
+        let has_ro = |options: &str| options.split(',').any(|o| o == "ro");
+        mountinfo
+            .lines()
+            .filter_map(|line| {
+                let (mount, fs) = line.split_once(" - ")?;
+                let mut mount = mount.split(' ').skip(4);
+                let mount_point = Self::unescape_mount_path(mount.next()?);
+                let mount_options = mount.next()?;
+                let mut fs = fs.split(' ');
+                let fs_type = fs.next()?.to_string();
+                let source = Self::unescape_mount_path(fs.next()?);
+                let super_options = fs.next().unwrap_or_default();
+                Some(MountedFs {
+                    source,
+                    device: None,
+                    mount_point: PathBuf::from(mount_point),
+                    fs_type,
+                    read_only: has_ro(mount_options) || has_ro(super_options),
+                })
+            })
+            .collect()
+    }
+
+    // The fields are narrower than `u64` on 32-bit targets.
+    #[allow(clippy::unnecessary_cast)]
+    fn statvfs(path: &Path) -> Option<FsSpace> {
+        let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+        let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+        if unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+            return None;
+        }
+        let stat = unsafe { stat.assume_init() };
+        let block = stat.f_frsize as u64;
+        Some(FsSpace {
+            total: (stat.f_blocks as u64).saturating_mul(block),
+            available: (stat.f_bavail as u64).saturating_mul(block),
+        })
+    }
+
+    fn path_meets(path: &Path, require: PathRequirements) -> bool {
+        let Ok(metadata) = fs::metadata(path) else {
+            return false;
+        };
+        if require.is_dir && !metadata.is_dir() {
+            return false;
+        }
+        if require.non_empty {
+            let non_empty = if metadata.is_dir() {
+                fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some())
+            } else {
+                metadata.len() > 0
+            };
+            if !non_empty {
+                return false;
+            }
+        }
+        if require.writable {
+            // access() also catches read-only mounts, unlike the mode bits.
+            let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+                return false;
+            };
+            if unsafe { libc::access(c_path.as_ptr(), libc::W_OK) } != 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Runs `check` on `path` in a helper thread and gives up after
+    /// `timeout`. Anything that touches a network filesystem (NFS, SMB,
+    /// sshfs...) can hang for minutes once its server is gone, and the thread
+    /// can't be cancelled; a path whose previous check is still stuck
+    /// therefore fails right away instead of piling up threads.
+    fn with_timeout<T: Send + 'static>(
+        &self,
+        path: &Path,
+        timeout: Duration,
+        check: impl FnOnce(&Path) -> T + Send + 'static,
+    ) -> Option<T> {
+        let path = path.to_path_buf();
+        if !self.stuck_paths.lock().unwrap().insert(path.clone()) {
+            return None;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let stuck_paths = Arc::clone(&self.stuck_paths);
+        thread::spawn(move || {
+            let result = check(&path);
+            stuck_paths.lock().unwrap().remove(&path);
+            let _ = tx.send(result);
+        });
+        rx.recv_timeout(timeout).ok()
+    }
+
+    fn is_network_fs(fs_type: &str) -> bool {
+        matches!(
+            fs_type,
+            "nfs"
+                | "nfs4"
+                | "cifs"
+                | "smb3"
+                | "smbfs"
+                | "ceph"
+                | "9p"
+                | "fuse.sshfs"
+                | "fuse.rclone"
+        )
+    }
+
+    /// Reads the `E:` properties udev recorded for a block device.
+    fn udev_properties(sys_path: &Path) -> HashMap<String, String> {
+        let Some(dev) = fs::read_to_string(sys_path.join("dev")).ok() else {
+            return HashMap::new();
+        };
+        let data_path = Path::new(UDEV_DATA_DIR).join(format!("b{}", dev.trim()));
+        fs::read_to_string(data_path)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.strip_prefix("E:")?.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    /// A partition inherits `removable` from its disk; USB disks often don't
+    /// set the flag at all, so being on the USB bus counts too.
+    fn is_removable(sys_path: &Path) -> bool {
+        let Ok(real_path) = fs::canonicalize(sys_path) else {
+            return false;
+        };
+        let flag =
+            |dir: &Path| fs::read_to_string(dir.join("removable")).is_ok_and(|s| s.trim() == "1");
+        flag(&real_path)
+            || real_path.parent().is_some_and(flag)
+            || real_path.to_string_lossy().contains("/usb")
+    }
+
+    /// Network filesystems count as virtual here; they are listed separately.
+    fn is_virtual_fs(fs_type: &str) -> bool {
+        // Filter out unwanted virtual/temporary filesystems by name
+        fs_type.starts_with("squashfs")
+            || fs_type.starts_with("overlay")
+            || fs_type.starts_with("tmpfs")
+            || fs_type.starts_with("devtmpfs")
+            || fs_type.starts_with("fuse.")
+            || fs_type == "swap"
+            || Self::is_network_fs(fs_type)
+    }
+
+    fn format_size(size_bytes: u64) -> String {
+        format!("{:.1} GB", size_bytes as f64 / 1_000_000_000.0)
+    }
+
     fn is_placeholder_file(path: &Path) -> bool {
         if let Ok(content) = fs::read_to_string(path) {
             content.contains("Managed by Conditional Launcher")
@@ -75,24 +638,236 @@ impl LinuxOperations {
 }
 
 impl OsOperations for LinuxOperations {
-    fn check_internet_connection(&self) -> bool {
-        match reqwest::blocking::get("http://connectivitycheck.gstatic.com/generate_204") {
-            Ok(response) => response.status().is_success(),
-            Err(_) => false,
+    fn check_internet_connection(&self, connectivity: &ConnectivityConfig) -> NetworkState {
+        let outcomes: Vec<_> = thread::scope(|scope| {
+            let handles: Vec<_> = connectivity
+                .probes
+                .iter()
+                .map(|probe| scope.spawn(|| Self::run_probe(probe)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or(ProbeOutcome::Fail))
+                .collect()
+        });
+
+        let passed = outcomes
+            .iter()
+            .filter(|&&o| o == ProbeOutcome::Pass)
+            .count();
+        if connectivity.pass.is_satisfied(passed, outcomes.len()) {
+            NetworkState::Online
+        } else if outcomes.contains(&ProbeOutcome::CaptivePortal) {
+            NetworkState::CaptivePortal
+        } else {
+            NetworkState::Offline
+        }
+    }
+
+    fn is_host_reachable(&self, host: &str, port: Option<u16>, timeout: Duration) -> bool {
+        match port {
+            Some(port) => Self::tcp_connect(host, port, timeout, false),
+            None => Self::tcp_connect(host, HOST_PROBE_PORT, timeout, true),
+        }
+    }
+
+    fn mounted_filesystems(&self) -> Vec<MountedFs> {
+        let mountinfo =
+            fs::read_to_string(self.procfs_root.join("self/mountinfo")).unwrap_or_default();
+        let mut mounts = Self::parse_mountinfo(&mountinfo);
+        for mount in &mut mounts {
+            if mount.source.starts_with("/dev/") {
+                mount.device = fs::canonicalize(&mount.source).ok();
+            }
+        }
+        mounts
+    }
+
+    fn partition_mount_point(
+        &self,
+        partition: &PartitionRef,
+        mounts: &[MountedFs],
+    ) -> Option<PathBuf> {
+        if let PartitionRef::Path(path) = partition {
+            let mount_path = Path::new(path);
+            let mounted = mounts.iter().any(|mount| mount.mount_point == mount_path);
+            return mounted.then(|| mount_path.to_path_buf());
+        }
+        let device = Self::device_for(partition)?;
+        mounts
+            .iter()
+            .find(|mount| mount.device.as_ref() == Some(&device))
+            .map(|mount| mount.mount_point.clone())
+    }
+
+    /// `/sys/...` and `/proc/...` are read from `sysfs_root` and
+    /// `procfs_root`, so fake trees work here too.
+    fn read_value(&self, path: &Path, timeout: Duration) -> Option<String> {
+        let path = if let Ok(rest) = path.strip_prefix("/sys") {
+            self.sysfs_root.join(rest)
+        } else if let Ok(rest) = path.strip_prefix("/proc") {
+            self.procfs_root.join(rest)
+        } else {
+            path.to_path_buf()
+        };
+        self.with_timeout(&path, timeout, |path| {
+            fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+        })
+        .flatten()
+    }
+
+    fn fs_space(&self, mount_point: &Path, timeout: Duration) -> Option<FsSpace> {
+        self.with_timeout(mount_point, timeout, Self::statvfs)
+            .flatten()
+    }
+
+    fn check_path(&self, path: &Path, require: PathRequirements, timeout: Duration) -> bool {
+        self.with_timeout(path, timeout, move |path| Self::path_meets(path, require))
+            .unwrap_or(false)
+    }
+
+    fn power_state(&self) -> PowerState {
+        let mut mains_online = None;
+        let mut batteries_discharging = false;
+        let (mut now, mut full) = (0u64, 0u64);
+        let mut capacities = Vec::new();
+
+        let supplies = fs::read_dir(self.sysfs_root.join("class/power_supply"))
+            .map(|entries| entries.filter_map(Result::ok).collect::<Vec<_>>())
+            .unwrap_or_default();
+        for supply in supplies {
+            let dir = supply.path();
+            let online = Self::read_sysfs(&dir, "online").map(|s| s == "1");
+            match Self::read_sysfs(&dir, "type").as_deref() {
+                Some("Mains") | Some("USB") => {
+                    mains_online = Some(mains_online.unwrap_or(false) || online == Some(true));
+                }
+                // Mice and headsets report their batteries with scope "Device".
+                Some("Battery") if Self::read_sysfs(&dir, "scope").as_deref() != Some("Device") => {
+                    batteries_discharging |=
+                        Self::read_sysfs(&dir, "status").as_deref() == Some("Discharging");
+                    let number = |attribute| {
+                        Self::read_sysfs(&dir, attribute).and_then(|s| s.parse::<u64>().ok())
+                    };
+                    match (
+                        number("energy_now").or_else(|| number("charge_now")),
+                        number("energy_full").or_else(|| number("charge_full")),
+                    ) {
+                        (Some(n), Some(f)) if f > 0 => {
+                            now += n;
+                            full += f;
+                        }
+                        _ => capacities.extend(number("capacity")),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let battery_percent = (now * 100)
+            .checked_div(full)
+            .or_else(|| {
+                capacities
+                    .iter()
+                    .sum::<u64>()
+                    .checked_div(capacities.len() as u64)
+            })
+            .map(|percent| percent.min(100) as u8);
+        let on_ac = match mains_online {
+            Some(online) => online,
+            None => battery_percent.is_none() || !batteries_discharging,
+        };
+
+        PowerState {
+            on_ac,
+            battery_percent,
+            power_saver: self.is_power_saver(),
         }
     }
 
-    fn is_partition_mounted(&self, path: &str, disks: &Disks) -> bool {
-        let mount_path = Path::new(path);
-        disks.iter().any(|disk| disk.mount_point() == mount_path)
+    fn system_load(&self) -> SystemLoad {
+        SystemLoad {
+            load1: fs::read_to_string(self.procfs_root.join("loadavg"))
+                .ok()
+                .and_then(|s| s.split(' ').next()?.parse().ok()),
+            cpu_pressure: self.read_pressure("cpu"),
+            io_pressure: self.read_pressure("io"),
+        }
     }
 
-    fn launch_app(&self, app: &AppConfig) {
+    fn run_check_command(&self, command: &str, timeout: Duration) -> CommandResult {
+        // Its own process group, so a timeout also takes down whatever the
+        // shell started.
+        let mut child = match Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                return CommandResult {
+                    success: false,
+                    stderr: e.to_string(),
+                };
+            }
+        };
+
+        // Read stderr on the side so a chatty command can't fill the pipe and
+        // stall; a background process keeping it open must not stall us either.
+        let (tx, rx) = mpsc::channel();
+        if let Some(mut stderr) = child.stderr.take() {
+            thread::spawn(move || {
+                let mut output = String::new();
+                let _ = stderr.read_to_string(&mut output);
+                let _ = tx.send(output);
+            });
+        }
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break Some(status),
+                Ok(None) if Instant::now() < deadline => thread::sleep(COMMAND_POLL_INTERVAL),
+                _ => {
+                    unsafe {
+                        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+                    }
+                    let _ = child.wait();
+                    break None;
+                }
+            }
+        };
+
+        let stderr = rx
+            .recv_timeout(
+                deadline.saturating_duration_since(Instant::now()) + COMMAND_POLL_INTERVAL,
+            )
+            .unwrap_or_default();
+        match status {
+            Some(status) => CommandResult {
+                success: status.success(),
+                stderr: stderr.trim().to_string(),
+            },
+            None => CommandResult {
+                success: false,
+                stderr: format!("Timed out after {} ms", timeout.as_millis()),
+            },
+        }
+    }
+
+    fn launch_app(&self, app: &AppConfig, mount_point: Option<&Path>) -> Option<Child> {
         let mut cmd = Command::new("sh");
         cmd.arg("-c").arg(&app.command);
         if let Some(dir) = &app.working_dir {
             cmd.current_dir(dir);
         }
+        if let Some(mount_point) = mount_point {
+            cmd.env(MOUNT_POINT_ENV, mount_point);
+        }
 
         unsafe {
             cmd.pre_exec(|| {
@@ -104,7 +879,44 @@ impl OsOperations for LinuxOperations {
             });
         }
 
-        let _ = cmd.stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+        cmd.stdout(Stdio::null()).stderr(Stdio::null()).spawn().ok()
+    }
+
+    fn stop_app(&self, pgid: u32, force: bool) {
+        let signal = if force { libc::SIGKILL } else { libc::SIGTERM };
+        unsafe {
+            libc::kill(-(pgid as libc::pid_t), signal);
+        }
+    }
+
+    fn is_process_group_alive(&self, pgid: u32) -> bool {
+        // Signal 0 only checks; EPERM still means something is there.
+        let result = unsafe { libc::kill(-(pgid as libc::pid_t), 0) };
+        result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    fn process_group_usage(&self, pgid: u32, sys: &System) -> ProcessUsage {
+        // Apps are started with `setsid`, so their session is their group,
+        // and sysinfo knows sessions. Threads are listed too but share their
+        // process's memory, so they are left out.
+        let session = Some(Pid::from_u32(pgid));
+        sys.processes()
+            .values()
+            .filter(|p| p.thread_kind().is_none() && p.session_id() == session)
+            .fold(ProcessUsage::default(), |usage, p| ProcessUsage {
+                memory: usage.memory + p.memory(),
+                cpu_percent: usage.cpu_percent + p.cpu_usage(),
+            })
+    }
+
+    fn notify(&self, summary: &str, body: &str) {
+        let mut cmd = Command::new("notify-send");
+        cmd.args(["--app-name", NOTIFY_APP_NAME, summary, body])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        // Waited for on the side, so a slow notification daemon can't hold
+        // up the daemon loop and the child doesn't linger as a zombie.
+        thread::spawn(move || cmd.status());
     }
 
     fn get_autostart_apps(&self) -> Vec<AppConfig> {
@@ -114,10 +926,10 @@ impl OsOperations for LinuxOperations {
             if let Ok(entries) = fs::read_dir(autostart_dir) {
                 for entry in entries.filter_map(Result::ok) {
                     let path = entry.path();
-                    if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                        if file_name == "conditional-launcher.desktop" {
-                            continue;
-                        }
+                    if let Some(file_name) = path.file_name().and_then(|n| n.to_str())
+                        && file_name == "conditional-launcher.desktop"
+                    {
+                        continue;
                     }
                     if path.extension().is_some_and(|e| e == "desktop") {
                         if Self::is_placeholder_file(&path) {
@@ -134,21 +946,21 @@ impl OsOperations for LinuxOperations {
     }
 
     fn manage_app(&self, app: &AppConfig) -> bool {
-        if let Some(original_path) = &app.original_path {
-            if let Some(backup_path) = Self::get_backup_path(original_path) {
-                if let Some(backup_dir) = Self::backup_directory() {
-                    if fs::create_dir_all(&backup_dir).is_err() {
-                        return false;
-                    }
-                }
+        if let Some(original_path) = &app.original_path
+            && let Some(backup_path) = Self::get_backup_path(original_path)
+        {
+            if let Some(backup_dir) = Self::backup_directory()
+                && fs::create_dir_all(&backup_dir).is_err()
+            {
+                return false;
+            }
 
-                if fs::rename(original_path, &backup_path).is_ok() {
-                    if Self::create_placeholder_desktop_file(original_path, app).is_ok() {
-                        return true;
-                    } else {
-                        let _ = fs::rename(&backup_path, original_path);
-                        return false;
-                    }
+            if fs::rename(original_path, &backup_path).is_ok() {
+                if Self::create_placeholder_desktop_file(original_path, app).is_ok() {
+                    return true;
+                } else {
+                    let _ = fs::rename(&backup_path, original_path);
+                    return false;
                 }
             }
         }
@@ -156,48 +968,221 @@ impl OsOperations for LinuxOperations {
     }
 
     fn unmanage_app(&self, app: &AppConfig) -> bool {
-        if let Some(original_path) = &app.original_path {
-            if let Some(backup_path) = Self::get_backup_path(original_path) {
-                if backup_path.exists() {
-                    let _ = fs::remove_file(original_path);
-                    return fs::rename(&backup_path, original_path).is_ok();
-                }
-            }
+        if let Some(original_path) = &app.original_path
+            && let Some(backup_path) = Self::get_backup_path(original_path)
+            && backup_path.exists()
+        {
+            let _ = fs::remove_file(original_path);
+            return fs::rename(&backup_path, original_path).is_ok();
         }
         false
     }
 
+    fn get_usb_devices(&self) -> Vec<UsbDeviceInfo> {
+        let Ok(entries) = fs::read_dir(self.sysfs_root.join("bus/usb/devices")) else {
+            return Vec::new();
+        };
+        let mut devices: Vec<_> = entries
+            .filter_map(Result::ok)
+            // `usbN` are root hubs and `1-2:1.0` interfaces; only devices
+            // like `1-2` or `1-2.4` are interesting.
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                !name.starts_with("usb") && !name.contains(':')
+            })
+            .filter_map(|entry| {
+                let dir = entry.path();
+                Some(UsbDeviceInfo {
+                    vendor_id: Self::read_sysfs(&dir, "idVendor")?.to_lowercase(),
+                    product_id: Self::read_sysfs(&dir, "idProduct")?.to_lowercase(),
+                    manufacturer: Self::read_sysfs(&dir, "manufacturer"),
+                    product: Self::read_sysfs(&dir, "product"),
+                })
+            })
+            .collect();
+        devices.sort_by_key(UsbDeviceInfo::id);
+        devices.dedup();
+        devices
+    }
+
+    fn get_displays(&self) -> Vec<DisplayInfo> {
+        let Ok(entries) = fs::read_dir(self.sysfs_root.join("class/drm")) else {
+            return Vec::new();
+        };
+        let mut displays: Vec<_> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                // Connectors are `card0-DP-1`; `card0` and `renderD128` are not.
+                let name = entry.file_name().to_string_lossy().to_string();
+                let (card, connector) = name.split_once('-')?;
+                if !card.starts_with("card") {
+                    return None;
+                }
+                let dir = entry.path();
+                if Self::read_sysfs(&dir, "status").as_deref() != Some("connected") {
+                    return None;
+                }
+                Some(DisplayInfo {
+                    connector: connector.to_string(),
+                    name: fs::read(dir.join("edid"))
+                        .ok()
+                        .and_then(|edid| Self::edid_monitor_name(&edid)),
+                })
+            })
+            .collect();
+        displays.sort_by(|a, b| a.connector.cmp(&b.connector));
+        displays
+    }
+
+    fn local_network(&self) -> LocalNetwork {
+        let arp = self.arp_table();
+        let gateway_macs = self
+            .default_gateways()
+            .iter()
+            .filter_map(|gateway| arp.get(gateway).cloned())
+            .collect();
+
+        let networks = Networks::new_with_refreshed_list();
+        let addresses = networks
+            .values()
+            .flat_map(|data| data.ip_networks())
+            .map(|network| (network.addr, network.prefix))
+            .collect();
+
+        // IFF_UP rather than operstate: tunnels like wg0 report "unknown".
+        let interfaces_up = fs::read_dir(self.sysfs_root.join("class/net"))
+            .map(|entries| entries.filter_map(Result::ok).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entry| {
+                Self::read_sysfs(&entry.path(), "flags")
+                    .and_then(|flags| u32::from_str_radix(flags.trim_start_matches("0x"), 16).ok())
+                    .is_some_and(|flags| flags & libc::IFF_UP as u32 != 0)
+            })
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+
+        LocalNetwork {
+            gateway_macs,
+            addresses,
+            interfaces_up,
+        }
+    }
+
     fn get_partitions(&self) -> Vec<PartitionInfo> {
-        let mut disks = Disks::new();
-        disks.refresh(true);
-        let mut partitions = Vec::new();
-        for disk in disks.iter() {
-            let mount_point_str = disk.mount_point().to_string_lossy();
-            if !mount_point_str.starts_with('/') {
-                continue; // Skip non-standard mount points
-            }
+        let mounts = self.mounted_filesystems();
+        let uuids = Self::device_ids("by-uuid");
+        let partuuids = Self::device_ids("by-partuuid");
+        let labels = Self::device_ids("by-label");
 
-            let fs_type_str = disk.file_system().to_string_lossy();
+        // Only mounts of a device node; the rest are pseudo filesystems or
+        // network shares.
+        let mut mounted: Vec<_> = mounts
+            .iter()
+            .filter(|mount| mount.source.starts_with('/') && !Self::is_network_fs(&mount.fs_type))
+            .map(|mount| {
+                let device = fs::canonicalize(&mount.source)
+                    .unwrap_or_else(|_| PathBuf::from(&mount.source));
+                (device, mount)
+            })
+            .collect();
+        // Only local filesystems are asked for their size here.
+        let total_space =
+            |mount: &MountedFs| Self::statvfs(&mount.mount_point).map_or(0, |s| s.total);
 
-            // Filter out unwanted virtual/temporary filesystems by name
-            if fs_type_str.starts_with("squashfs")
-                || fs_type_str.starts_with("overlay")
-                || fs_type_str.starts_with("tmpfs")
-                || fs_type_str.starts_with("devtmpfs")
-                || fs_type_str.starts_with("fuse.")
-            {
+        let mut block_devices: Vec<_> = fs::read_dir(self.sysfs_root.join("class/block"))
+            .map(|entries| entries.filter_map(Result::ok).collect())
+            .unwrap_or_default();
+        block_devices.sort_by_key(|entry| entry.file_name());
+
+        let mut partitions = Vec::new();
+        for entry in block_devices {
+            let sys_path = entry.path();
+            let device = Path::new("/dev").join(entry.file_name());
+            let udev = Self::udev_properties(&sys_path);
+            let mount = mounted
+                .iter()
+                .position(|(d, _)| *d == device)
+                .map(|i| mounted.remove(i).1);
+
+            let fs_type = mount
+                .map(|mount| mount.fs_type.clone())
+                .or_else(|| udev.get("ID_FS_TYPE").cloned())
+                .unwrap_or_default();
+            // Whole disks holding a partition table have no filesystem.
+            if fs_type.is_empty() || Self::is_virtual_fs(&fs_type) {
                 continue;
             }
 
-            let mount_point = mount_point_str.to_string();
-            let size_bytes = disk.total_space();
-            let size = format!("{:.1} GB", size_bytes as f64 / 1_000_000_000.0);
-            let fs_type = fs_type_str.to_string();
+            let size_bytes = match mount {
+                Some(mount) => total_space(mount),
+                None => fs::read_to_string(sys_path.join("size"))
+                    .ok()
+                    .and_then(|s| s.trim().parse::<u64>().ok())
+                    .unwrap_or_default()
+                    .saturating_mul(512),
+            };
 
             partitions.push(PartitionInfo {
-                mount_point,
+                device: device.to_string_lossy().to_string(),
+                mount_point: mount.map(|mount| mount.mount_point.to_string_lossy().to_string()),
                 fs_type,
-                size,
+                size: Self::format_size(size_bytes),
+                uuid: uuids
+                    .get(&device)
+                    .cloned()
+                    .or_else(|| udev.get("ID_FS_UUID").cloned()),
+                partuuid: partuuids
+                    .get(&device)
+                    .cloned()
+                    .or_else(|| udev.get("ID_PART_ENTRY_UUID").cloned()),
+                label: labels
+                    .get(&device)
+                    .cloned()
+                    .or_else(|| udev.get("ID_FS_LABEL").cloned()),
+                removable: Self::is_removable(&sys_path),
+                network: false,
+            });
+        }
+
+        // Mounts without a local block device behind them.
+        for (device, mount) in mounted {
+            if Self::is_virtual_fs(&mount.fs_type) {
+                continue;
+            }
+            partitions.push(PartitionInfo {
+                device: device.to_string_lossy().to_string(),
+                mount_point: Some(mount.mount_point.to_string_lossy().to_string()),
+                fs_type: mount.fs_type.clone(),
+                size: Self::format_size(total_space(mount)),
+                uuid: None,
+                partuuid: None,
+                label: None,
+                removable: false,
+                network: false,
+            });
+        }
+
+        for mount in mounts {
+            let mount_point = mount.mount_point.to_string_lossy().to_string();
+            if !Self::is_network_fs(&mount.fs_type)
+                || partitions
+                    .iter()
+                    .any(|p| p.mount_point.as_ref() == Some(&mount_point))
+            {
+                continue;
+            }
+            partitions.push(PartitionInfo {
+                device: mount.source,
+                mount_point: Some(mount_point),
+                fs_type: mount.fs_type,
+                size: String::new(),
+                uuid: None,
+                partuuid: None,
+                label: None,
+                removable: false,
+                network: true,
             });
         }
         partitions
@@ -225,10 +1210,10 @@ impl OsOperations for LinuxOperations {
     }
 
     fn remove_self_from_autostart(&self) {
-        if let Some(path) = Self::launcher_desktop_file_path() {
-            if path.exists() {
-                fs::remove_file(path).ok();
-            }
+        if let Some(path) = Self::launcher_desktop_file_path()
+            && path.exists()
+        {
+            fs::remove_file(path).ok();
         }
     }
 
@@ -239,14 +1224,12 @@ impl OsOperations for LinuxOperations {
             .next()
             .and_then(|p| Path::new(p).file_name())
             .and_then(|f| f.to_str())
-        {
-            if sys
+            && sys
                 .processes_by_name(process_name.as_ref())
                 .next()
                 .is_some()
-            {
-                return true;
-            }
+        {
+            return true;
         }
 
         if sys.processes_by_name(app.name.as_ref()).next().is_some() {
@@ -263,4 +1246,529 @@ impl OsOperations for LinuxOperations {
 
         false
     }
+
+    fn is_process_running(&self, process: &ProcessMatch, sys: &System) -> bool {
+        match process {
+            ProcessMatch::Name(name) => sys.processes_by_exact_name(name.as_ref()).next().is_some(),
+            ProcessMatch::Exe(exe) => {
+                let exe = Path::new(exe);
+                sys.processes().values().any(|p| p.exe() == Some(exe))
+            }
+            ProcessMatch::Cmdline(pattern) => sys.processes().values().any(|p| {
+                let cmdline = p
+                    .cmd()
+                    .iter()
+                    .map(|arg| arg.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                pattern.is_match(&cmdline)
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProbeRule;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn read_value_maps_sys_and_proc_onto_fake_roots() {
+        let root = TempDir::new().unwrap();
+        write(
+            root.path(),
+            "sys/class/thermal/thermal_zone0/temp",
+            "45000\n",
+        );
+        write(
+            root.path(),
+            "proc/acpi/button/lid/LID0/state",
+            "state:      open\n",
+        );
+        write(root.path(), "elsewhere", "  as is  ");
+        let os = LinuxOperations::with_root(root.path());
+        let timeout = Duration::from_secs(5);
+
+        assert_eq!(
+            os.read_value(Path::new("/sys/class/thermal/thermal_zone0/temp"), timeout),
+            Some("45000".to_string())
+        );
+        assert_eq!(
+            os.read_value(Path::new("/proc/acpi/button/lid/LID0/state"), timeout),
+            Some("state:      open".to_string())
+        );
+        assert_eq!(
+            os.read_value(&root.path().join("elsewhere"), timeout),
+            Some("as is".to_string())
+        );
+        assert_eq!(os.read_value(Path::new("/sys/missing"), timeout), None);
+    }
+
+    /// Serves `responses` to one connection each and returns the URL.
+    fn http_stand_in(responses: &[&'static str]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/generate_204", listener.local_addr().unwrap());
+        let responses = responses.to_vec();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        url
+    }
+
+    fn http(url: &str, expected_status: Option<u16>, expected_body: Option<&str>) -> ProbeOutcome {
+        LinuxOperations::probe_http(url, expected_status, expected_body, Duration::from_secs(5))
+    }
+
+    #[test]
+    fn http_probe_classifies_responses() {
+        let url = http_stand_in(&[
+            "HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nlogin",
+            "HTTP/1.1 302 Found\r\nLocation: http://portal/\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\nsuccess",
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nlogin",
+            "HTTP/1.1 301 Moved Permanently\r\nLocation: https://x/\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 301 Moved Permanently\r\nLocation: https://x/\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 302 Found\r\nLocation: http://portal/\r\nContent-Length: 0\r\n\r\n",
+        ]);
+        assert_eq!(http(&url, Some(204), None), ProbeOutcome::Pass);
+        assert_eq!(http(&url, Some(204), None), ProbeOutcome::CaptivePortal);
+        assert_eq!(http(&url, Some(204), None), ProbeOutcome::CaptivePortal);
+        assert_eq!(http(&url, None, None), ProbeOutcome::Fail);
+        assert_eq!(http(&url, None, Some("success")), ProbeOutcome::Pass);
+        assert_eq!(
+            http(&url, None, Some("success")),
+            ProbeOutcome::CaptivePortal
+        );
+        // A redirect that is expected passes, and one from a URL that can't
+        // tell a portal apart just fails.
+        assert_eq!(http(&url, Some(301), None), ProbeOutcome::Pass);
+        assert_eq!(http(&url, None, None), ProbeOutcome::Fail);
+        assert_eq!(
+            http(&url, None, Some("success")),
+            ProbeOutcome::CaptivePortal
+        );
+    }
+
+    #[test]
+    fn http_probe_fails_without_a_server() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = format!("http://127.0.0.1:{port}/");
+        assert_eq!(http(&url, None, None), ProbeOutcome::Fail);
+    }
+
+    /// Answers one query with `rcode` and `answers` records claimed in the
+    /// header, and returns the server address.
+    fn dns_stand_in(rcode: u8, answers: u16) -> String {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            let (len, from) = socket.recv_from(&mut buf).unwrap();
+            let mut response = buf[..len].to_vec();
+            response[2] = 0x81;
+            response[3] = 0x80 | rcode;
+            response[6..8].copy_from_slice(&answers.to_be_bytes());
+            socket.send_to(&response, from).unwrap();
+        });
+        addr
+    }
+
+    #[test]
+    fn dns_probe_needs_an_answer() {
+        let timeout = Duration::from_secs(5);
+        let dns = |server: &str| LinuxOperations::probe_dns_server("example.com", server, timeout);
+        assert!(dns(&dns_stand_in(0, 1)));
+        assert!(!dns(&dns_stand_in(3, 0)));
+        assert!(!dns(&dns_stand_in(0, 0)));
+        assert!(!LinuxOperations::probe_dns_server(
+            "example.com",
+            "not an address",
+            timeout
+        ));
+    }
+
+    #[test]
+    fn connectivity_applies_the_pass_rule() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let tcp = |port| Probe::Tcp {
+            host: "127.0.0.1".to_string(),
+            port,
+            timeout_ms: 2000,
+        };
+        let os = LinuxOperations::default();
+        let state =
+            |pass, probes| os.check_internet_connection(&ConnectivityConfig { pass, probes });
+
+        let probes = vec![tcp(open), tcp(closed)];
+        assert_eq!(state(ProbeRule::Any, probes.clone()), NetworkState::Online);
+        assert_eq!(state(ProbeRule::All, probes.clone()), NetworkState::Offline);
+        assert_eq!(
+            state(ProbeRule::AtLeast(1), probes.clone()),
+            NetworkState::Online
+        );
+        assert_eq!(state(ProbeRule::AtLeast(2), probes), NetworkState::Offline);
+        assert_eq!(state(ProbeRule::All, vec![tcp(open)]), NetworkState::Online);
+        assert_eq!(state(ProbeRule::All, Vec::new()), NetworkState::Offline);
+
+        // A portal only counts when the rule isn't met without it.
+        let portal = || Probe::Http {
+            url: http_stand_in(&["HTTP/1.1 302 Found\r\nContent-Length: 0\r\n\r\n"]),
+            expected_status: Some(204),
+            expected_body: None,
+            timeout_ms: 2000,
+        };
+        assert_eq!(
+            state(ProbeRule::Any, vec![tcp(open), portal()]),
+            NetworkState::Online
+        );
+        assert_eq!(
+            state(ProbeRule::All, vec![tcp(open), portal()]),
+            NetworkState::CaptivePortal
+        );
+    }
+
+    #[test]
+    fn mountinfo_lists_mounts_without_touching_them() {
+        let root = TempDir::new().unwrap();
+        write(
+            root.path(),
+            "proc/self/mountinfo",
+            "26 1 259:2 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw\n\
+             90 26 8:17 / /run/media/me/My\\040Disk ro,nosuid shared:50 - exfat /dev/sdb1 rw\n\
+             95 26 8:18 / /mnt/games rw,relatime - btrfs /dev/sdb2 ro,space_cache\n\
+             99 26 0:60 / /mnt/nas rw,relatime shared:60 - nfs4 nas:/export rw,hard\n",
+        );
+        let mounts = LinuxOperations::with_root(root.path()).mounted_filesystems();
+        let summary: Vec<_> = mounts
+            .iter()
+            .map(|m| {
+                (
+                    m.source.as_str(),
+                    m.mount_point.to_str().unwrap(),
+                    m.fs_type.as_str(),
+                    m.read_only,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("/dev/nvme0n1p2", "/", "ext4", false),
+                ("/dev/sdb1", "/run/media/me/My Disk", "exfat", true),
+                ("/dev/sdb2", "/mnt/games", "btrfs", true),
+                ("nas:/export", "/mnt/nas", "nfs4", false),
+            ]
+        );
+
+        let os = LinuxOperations::with_root(root.path());
+        let nas = PartitionRef::Path("/mnt/nas".to_string());
+        assert_eq!(
+            os.partition_mount_point(&nas, &mounts),
+            Some(PathBuf::from("/mnt/nas"))
+        );
+        let gone = PartitionRef::Path("/mnt/gone".to_string());
+        assert_eq!(os.partition_mount_point(&gone, &mounts), None);
+    }
+
+    #[test]
+    fn check_path_requirements() {
+        let root = TempDir::new().unwrap();
+        write(root.path(), "full/file", "data");
+        write(root.path(), "empty-file", "");
+        fs::create_dir(root.path().join("empty")).unwrap();
+        let os = LinuxOperations::default();
+        let timeout = Duration::from_secs(5);
+        let check = |path: &str, is_dir, non_empty| {
+            let require = PathRequirements {
+                is_dir,
+                non_empty,
+                writable: true,
+            };
+            os.check_path(&root.path().join(path), require, timeout)
+        };
+
+        assert!(check("full", true, true));
+        assert!(check("full/file", false, true));
+        assert!(!check("full/file", true, false));
+        assert!(check("empty", true, false));
+        assert!(!check("empty", true, true));
+        assert!(!check("empty-file", false, true));
+        assert!(!check("missing", false, false));
+    }
+
+    #[test]
+    fn hung_checks_time_out_and_are_not_piled_up() {
+        let os = LinuxOperations::default();
+        let hung = Path::new("/mnt/dead-share");
+        let (release, wait) = mpsc::channel::<()>();
+        let started = Instant::now();
+        let result = os.with_timeout(hung, Duration::from_millis(50), move |_| {
+            let _ = wait.recv();
+        });
+        assert_eq!(result, None);
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Still stuck: no second thread, even for a check that would answer.
+        assert_eq!(os.with_timeout(hung, Duration::from_secs(1), |_| ()), None);
+        assert_eq!(
+            os.with_timeout(Path::new("/elsewhere"), Duration::from_secs(1), |_| 7),
+            Some(7)
+        );
+
+        release.send(()).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while os.stuck_paths.lock().unwrap().contains(hung) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(
+            os.with_timeout(hung, Duration::from_secs(1), |_| 1),
+            Some(1)
+        );
+
+        let space = os.fs_space(Path::new("/"), Duration::from_secs(5)).unwrap();
+        assert!(space.total > 0 && space.available <= space.total);
+    }
+
+    fn supply(root: &Path, name: &str, attributes: &[(&str, &str)]) {
+        for (attribute, value) in attributes {
+            write(
+                root,
+                &format!("sys/class/power_supply/{name}/{attribute}"),
+                value,
+            );
+        }
+    }
+
+    #[test]
+    fn system_load_reads_loadavg_and_pressure() {
+        let root = TempDir::new().unwrap();
+        write(root.path(), "proc/loadavg", "1.52 0.98 0.61 2/1234 5678\n");
+        write(
+            root.path(),
+            "proc/pressure/cpu",
+            "some avg10=12.50 avg60=3.00 avg300=1.00 total=123\n\
+             full avg10=0.00 avg60=0.00 avg300=0.00 total=0\n",
+        );
+        let load = LinuxOperations::with_root(root.path()).system_load();
+        assert_eq!(
+            load,
+            SystemLoad {
+                load1: Some(1.52),
+                cpu_pressure: Some(12.5),
+                // No PSI for io, e.g. a kernel built without it.
+                io_pressure: None,
+            }
+        );
+    }
+
+    #[test]
+    fn local_network_finds_the_gateway_mac_and_interfaces_up() {
+        let root = TempDir::new().unwrap();
+        // 192.168.1.1 as the default gateway, printed as the kernel does on a
+        // little-endian machine; the 10.0.0.0/8 route has no gateway.
+        write(
+            root.path(),
+            "proc/net/route",
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\n\
+             wlan0\t0000000A\t00000000\t0001\t0\t0\t0\t000000FF\n\
+             wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\n",
+        );
+        write(
+            root.path(),
+            "proc/net/arp",
+            "IP address       HW type     Flags       HW address            Mask     Device\n\
+             192.168.1.1      0x1         0x2         A4:2B:B0:12:34:56     *        wlan0\n\
+             192.168.1.7      0x1         0x0         00:00:00:00:00:00     *        wlan0\n",
+        );
+        write(root.path(), "sys/class/net/wlan0/flags", "0x1003\n");
+        write(root.path(), "sys/class/net/wg0/flags", "0x1002\n");
+        let network = LinuxOperations::with_root(root.path()).local_network();
+
+        if cfg!(target_endian = "little") {
+            assert_eq!(network.gateway_macs, ["a4:2b:b0:12:34:56"]);
+        }
+        assert_eq!(network.interfaces_up, ["wlan0"]);
+    }
+
+    /// A 128-byte EDID base block with a detailed timing first and the
+    /// monitor name in the second descriptor.
+    fn edid(name: &str) -> Vec<u8> {
+        let mut edid = vec![0u8; 128];
+        edid[..8].copy_from_slice(&[0, 255, 255, 255, 255, 255, 255, 0]);
+        edid[54..56].copy_from_slice(&[0x02, 0x3a]);
+        edid[72..77].copy_from_slice(&[0, 0, 0, 0xFC, 0]);
+        let mut text = format!("{name}\n").into_bytes();
+        text.resize(13, b' ');
+        edid[77..90].copy_from_slice(&text);
+        edid
+    }
+
+    #[test]
+    fn edid_monitor_name_reads_the_name_descriptor() {
+        assert_eq!(
+            LinuxOperations::edid_monitor_name(&edid("DELL U2720Q")),
+            Some("DELL U2720Q".to_string())
+        );
+        assert_eq!(LinuxOperations::edid_monitor_name(&[0u8; 128]), None);
+        assert_eq!(LinuxOperations::edid_monitor_name(&[]), None);
+    }
+
+    #[test]
+    fn get_displays_lists_connected_connectors() {
+        let root = TempDir::new().unwrap();
+        let drm = root.path().join("sys/class/drm");
+        write(&drm, "card0/dev", "226:0\n");
+        write(&drm, "renderD128/dev", "226:128\n");
+        write(&drm, "card0-eDP-1/status", "connected\n");
+        write(&drm, "card0-DP-1/status", "connected\n");
+        fs::write(drm.join("card0-DP-1/edid"), edid("DELL U2720Q")).unwrap();
+        write(&drm, "card0-HDMI-A-1/status", "disconnected\n");
+
+        assert_eq!(
+            LinuxOperations::with_root(root.path()).get_displays(),
+            [
+                DisplayInfo {
+                    connector: "DP-1".to_string(),
+                    name: Some("DELL U2720Q".to_string()),
+                },
+                DisplayInfo {
+                    connector: "eDP-1".to_string(),
+                    name: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn power_state_without_supplies_is_on_ac() {
+        let root = TempDir::new().unwrap();
+        assert_eq!(
+            LinuxOperations::with_root(root.path()).power_state(),
+            PowerState::default()
+        );
+    }
+
+    #[test]
+    fn power_state_prefers_mains_and_sums_battery_energy() {
+        let root = TempDir::new().unwrap();
+        supply(root.path(), "AC", &[("type", "Mains"), ("online", "0")]);
+        supply(
+            root.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Charging"),
+                ("energy_now", "30000000"),
+                ("energy_full", "40000000"),
+                ("capacity", "99"),
+            ],
+        );
+        supply(
+            root.path(),
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("charge_now", "0"),
+                ("charge_full", "10000000"),
+            ],
+        );
+        let state = LinuxOperations::with_root(root.path()).power_state();
+        // Mains says offline even though a battery reports charging.
+        assert!(!state.on_ac);
+        assert_eq!(state.battery_percent, Some(60));
+
+        supply(root.path(), "AC", &[("online", "1")]);
+        assert!(LinuxOperations::with_root(root.path()).power_state().on_ac);
+    }
+
+    #[test]
+    fn power_state_falls_back_to_capacity_and_ignores_device_batteries() {
+        let root = TempDir::new().unwrap();
+        supply(
+            root.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "35"),
+            ],
+        );
+        supply(
+            root.path(),
+            "hidpp_battery_0",
+            &[
+                ("type", "Battery"),
+                ("scope", "Device"),
+                ("status", "Discharging"),
+                ("capacity", "5"),
+            ],
+        );
+        let state = LinuxOperations::with_root(root.path()).power_state();
+        // No mains supply: a discharging battery means unplugged.
+        assert!(!state.on_ac);
+        assert_eq!(state.battery_percent, Some(35));
+
+        supply(root.path(), "BAT0", &[("status", "Full")]);
+        assert!(LinuxOperations::with_root(root.path()).power_state().on_ac);
+    }
+
+    #[test]
+    fn power_saver_prefers_power_profiles_daemon() {
+        let root = TempDir::new().unwrap();
+        let os = LinuxOperations::with_root(root.path());
+        assert!(!os.power_state().power_saver);
+
+        write(
+            root.path(),
+            "sys/firmware/acpi/platform_profile",
+            "low-power\n",
+        );
+        assert!(os.power_state().power_saver);
+
+        write(
+            root.path(),
+            POWER_PROFILES_STATE,
+            "[State]\nProfile=balanced\nCpuDriver=amd_pstate\n",
+        );
+        assert!(!os.power_state().power_saver);
+
+        write(
+            root.path(),
+            POWER_PROFILES_STATE,
+            "[State]\nProfile=power-saver\n",
+        );
+        assert!(os.power_state().power_saver);
+    }
 }
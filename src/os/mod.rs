@@ -1,45 +1,303 @@
-use crate::config::AppConfig;
-use sysinfo::{Disks, System};
+use crate::config::{
+    AppConfig, ConnectivityConfig, DisplayRef, NetworkMatch, PartitionRef, PathRequirements,
+    ProcessMatch, UsbDeviceRef,
+};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::time::Duration;
+use sysinfo::System;
 
 #[cfg(target_os = "linux")]
 pub mod linux;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum NetworkState {
+    Online,
+    /// HTTP probes were redirected or answered with a login page.
+    CaptivePortal,
+    #[default]
+    Offline,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PowerState {
+    /// Also true when there is no battery at all.
+    pub on_ac: bool,
+    /// Combined charge of all system batteries, `None` without any.
+    pub battery_percent: Option<u8>,
+    pub power_saver: bool,
+}
+
+impl Default for PowerState {
+    fn default() -> Self {
+        Self {
+            on_ac: true,
+            battery_percent: None,
+            power_saver: false,
+        }
+    }
+}
+
+/// Unknown values (e.g. a kernel without PSI) are `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SystemLoad {
+    pub load1: Option<f64>,
+    pub cpu_pressure: Option<f64>,
+    pub io_pressure: Option<f64>,
+}
+
+/// Outcome of a `command` condition. `stderr` (or the reason it couldn't run)
+/// is kept so the GUI can show why the check failed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandResult {
+    pub success: bool,
+    pub stderr: String,
+}
+
+/// Resources used by everything in a process group.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ProcessUsage {
+    /// Resident memory in bytes.
+    pub memory: u64,
+    /// In percent of one core.
+    pub cpu_percent: f32,
+}
+
+/// A mounted filesystem as the kernel lists it, without touching it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MountedFs {
+    pub source: String,
+    /// The device node `source` resolves to, for sources under `/dev`.
+    pub device: Option<PathBuf>,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+    pub read_only: bool,
+}
+
+/// In bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FsSpace {
+    pub total: u64,
+    pub available: u64,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct PartitionInfo {
-    pub mount_point: String,
+    pub device: String,
+    pub mount_point: Option<String>,
     pub fs_type: String,
     pub size: String,
+    pub uuid: Option<String>,
+    pub partuuid: Option<String>,
+    pub label: Option<String>,
+    pub removable: bool,
+    /// NFS, SMB, sshfs and the like.
+    pub network: bool,
+}
+
+impl PartitionInfo {
+    /// Prefers the filesystem UUID, so the condition survives the partition
+    /// being mounted somewhere else next time.
+    pub fn partition_ref(&self) -> Option<PartitionRef> {
+        if let Some(uuid) = &self.uuid {
+            Some(PartitionRef::Uuid(uuid.clone()))
+        } else if let Some(partuuid) = &self.partuuid {
+            Some(PartitionRef::Partuuid(partuuid.clone()))
+        } else if let Some(label) = &self.label {
+            Some(PartitionRef::Label(label.clone()))
+        } else {
+            self.mount_point.clone().map(PartitionRef::Path)
+        }
+    }
+
+    pub fn matches(&self, partition: &PartitionRef) -> bool {
+        match partition {
+            PartitionRef::Path(path) => self.mount_point.as_ref() == Some(path),
+            PartitionRef::Uuid(uuid) => self.uuid.as_ref() == Some(uuid),
+            PartitionRef::Partuuid(partuuid) => self.partuuid.as_ref() == Some(partuuid),
+            PartitionRef::Label(label) => self.label.as_ref() == Some(label),
+        }
+    }
+}
+
+/// A connected USB device; IDs are lowercase hex as in sysfs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UsbDeviceInfo {
+    pub vendor_id: String,
+    pub product_id: String,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+}
+
+impl UsbDeviceInfo {
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.vendor_id, self.product_id)
+    }
+
+    pub fn matches(&self, device: &UsbDeviceRef) -> bool {
+        match device {
+            UsbDeviceRef::Id(id) => self.id().eq_ignore_ascii_case(id.trim()),
+            UsbDeviceRef::Product(product) => self
+                .product
+                .as_ref()
+                .is_some_and(|p| p.to_lowercase().contains(&product.to_lowercase())),
+        }
+    }
+}
+
+/// What identifies the network the machine is on.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LocalNetwork {
+    /// MAC addresses of the default gateways, lowercase.
+    pub gateway_macs: Vec<String>,
+    /// Address and prefix length of every interface.
+    pub addresses: Vec<(IpAddr, u8)>,
+    pub interfaces_up: Vec<String>,
+}
+
+impl LocalNetwork {
+    pub fn matches(&self, network: &NetworkMatch) -> bool {
+        match network {
+            NetworkMatch::GatewayMac(mac) => self.gateway_macs.iter().any(|m| mac.matches(m)),
+            NetworkMatch::Subnet(subnet) => self
+                .addresses
+                .iter()
+                .any(|&(addr, _)| subnet.contains(addr)),
+            NetworkMatch::InterfaceUp(interface) => self.interfaces_up.contains(interface),
+        }
+    }
+}
+
+/// A connected display.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DisplayInfo {
+    pub connector: String,
+    /// Monitor name from the EDID, if it has one.
+    pub name: Option<String>,
+}
+
+impl DisplayInfo {
+    pub fn matches(&self, display: &DisplayRef) -> bool {
+        match display {
+            DisplayRef::Connector(connector) => self.connector.eq_ignore_ascii_case(connector),
+            DisplayRef::Name(name) => self
+                .name
+                .as_ref()
+                .is_some_and(|n| n.to_lowercase().contains(&name.to_lowercase())),
+        }
+    }
 }
 
 pub trait OsOperations {
-    fn check_internet_connection(&self) -> bool;
-    fn is_partition_mounted(&self, path: &str, disks: &Disks) -> bool;
-    fn launch_app(&self, app: &AppConfig);
+    fn check_internet_connection(&self, connectivity: &ConnectivityConfig) -> NetworkState;
+    fn is_host_reachable(&self, host: &str, port: Option<u16>, timeout: Duration) -> bool;
+    fn mounted_filesystems(&self) -> Vec<MountedFs>;
+    fn partition_mount_point(
+        &self,
+        partition: &PartitionRef,
+        mounts: &[MountedFs],
+    ) -> Option<PathBuf>;
+    /// False when the path doesn't answer within `timeout`.
+    fn check_path(&self, path: &Path, require: PathRequirements, timeout: Duration) -> bool;
+    /// Trimmed contents of a small file such as a sysfs attribute, `None`
+    /// when it can't be read within `timeout`.
+    fn read_value(&self, path: &Path, timeout: Duration) -> Option<String>;
+    /// `None` when the filesystem doesn't answer within `timeout`.
+    fn fs_space(&self, mount_point: &Path, timeout: Duration) -> Option<FsSpace>;
+    fn power_state(&self) -> PowerState;
+    fn system_load(&self) -> SystemLoad;
+    fn run_check_command(&self, command: &str, timeout: Duration) -> CommandResult;
+    /// The child heads its own process group, so everything the app starts
+    /// can be found through `child.id()`.
+    fn launch_app(&self, app: &AppConfig, mount_point: Option<&Path>) -> Option<Child>;
+    /// Asks every process in the group to quit, or kills them with `force`.
+    fn stop_app(&self, pgid: u32, force: bool);
+    fn is_process_group_alive(&self, pgid: u32) -> bool;
+    /// `sys` needs memory and CPU usage refreshed, twice for the latter.
+    fn process_group_usage(&self, pgid: u32, sys: &System) -> ProcessUsage;
+    /// Desktop notification; failures are ignored.
+    fn notify(&self, summary: &str, body: &str);
     fn get_autostart_apps(&self) -> Vec<AppConfig>;
     fn manage_app(&self, app: &AppConfig) -> bool;
     fn unmanage_app(&self, app: &AppConfig) -> bool;
     fn get_partitions(&self) -> Vec<PartitionInfo>;
+    fn get_usb_devices(&self) -> Vec<UsbDeviceInfo>;
+    fn get_displays(&self) -> Vec<DisplayInfo>;
+    fn local_network(&self) -> LocalNetwork;
     fn add_self_to_autostart(&self, managed_app_count: usize);
     fn remove_self_from_autostart(&self);
     fn is_app_running(&self, app: &AppConfig, sys: &System) -> bool;
+    fn is_process_running(&self, process: &ProcessMatch, sys: &System) -> bool;
 }
 
 pub fn get_os_operations() -> Box<dyn OsOperations> {
     #[cfg(target_os = "linux")]
     {
-        Box::new(linux::LinuxOperations)
+        Box::new(linux::LinuxOperations::default())
     }
     #[cfg(not(target_os = "linux"))]
     {
         struct UnsupportedOperations;
         impl OsOperations for UnsupportedOperations {
-            fn check_internet_connection(&self) -> bool {
+            fn check_internet_connection(
+                &self,
+                _connectivity: &ConnectivityConfig,
+            ) -> NetworkState {
+                NetworkState::Offline
+            }
+            fn is_host_reachable(
+                &self,
+                _host: &str,
+                _port: Option<u16>,
+                _timeout: Duration,
+            ) -> bool {
                 false
             }
-            fn is_partition_mounted(&self, _path: &str, _disks: &Disks) -> bool {
+            fn mounted_filesystems(&self) -> Vec<MountedFs> {
+                Vec::new()
+            }
+            fn partition_mount_point(
+                &self,
+                _partition: &PartitionRef,
+                _mounts: &[MountedFs],
+            ) -> Option<PathBuf> {
+                None
+            }
+            fn check_path(
+                &self,
+                _path: &Path,
+                _require: PathRequirements,
+                _timeout: Duration,
+            ) -> bool {
                 false
             }
-            fn launch_app(&self, _app: &AppConfig) {}
+            fn fs_space(&self, _mount_point: &Path, _timeout: Duration) -> Option<FsSpace> {
+                None
+            }
+            fn read_value(&self, _path: &Path, _timeout: Duration) -> Option<String> {
+                None
+            }
+            fn power_state(&self) -> PowerState {
+                PowerState::default()
+            }
+            fn system_load(&self) -> SystemLoad {
+                SystemLoad::default()
+            }
+            fn run_check_command(&self, _command: &str, _timeout: Duration) -> CommandResult {
+                CommandResult::default()
+            }
+            fn launch_app(&self, _app: &AppConfig, _mount_point: Option<&Path>) -> Option<Child> {
+                None
+            }
+            fn stop_app(&self, _pgid: u32, _force: bool) {}
+            fn is_process_group_alive(&self, _pgid: u32) -> bool {
+                false
+            }
+            fn process_group_usage(&self, _pgid: u32, _sys: &System) -> ProcessUsage {
+                ProcessUsage::default()
+            }
+            fn notify(&self, _summary: &str, _body: &str) {}
             fn get_autostart_apps(&self) -> Vec<AppConfig> {
                 vec![]
             }
@@ -49,6 +307,15 @@ pub fn get_os_operations() -> Box<dyn OsOperations> {
             fn unmanage_app(&self, _app: &AppConfig) -> bool {
                 false
             }
+            fn get_usb_devices(&self) -> Vec<UsbDeviceInfo> {
+                Vec::new()
+            }
+            fn get_displays(&self) -> Vec<DisplayInfo> {
+                Vec::new()
+            }
+            fn local_network(&self) -> LocalNetwork {
+                LocalNetwork::default()
+            }
             fn get_partitions(&self) -> Vec<PartitionInfo> {
                 vec![]
             }
@@ -57,6 +324,9 @@ pub fn get_os_operations() -> Box<dyn OsOperations> {
             fn is_app_running(&self, _app: &AppConfig, _sys: &System) -> bool {
                 false
             }
+            fn is_process_running(&self, _process: &ProcessMatch, _sys: &System) -> bool {
+                false
+            }
         }
         Box::new(UnsupportedOperations)
     }
@@ -0,0 +1,137 @@
+use crate::config::{Condition, Conditions, Schedule, Weekday};
+use chrono::{Datelike, Days, NaiveDateTime, NaiveTime, Timelike};
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+fn to_chrono(day: Weekday) -> chrono::Weekday {
+    match day {
+        Weekday::Mon => chrono::Weekday::Mon,
+        Weekday::Tue => chrono::Weekday::Tue,
+        Weekday::Wed => chrono::Weekday::Wed,
+        Weekday::Thu => chrono::Weekday::Thu,
+        Weekday::Fri => chrono::Weekday::Fri,
+        Weekday::Sat => chrono::Weekday::Sat,
+        Weekday::Sun => chrono::Weekday::Sun,
+    }
+}
+
+fn allows_day(schedule: &Schedule, day: chrono::Weekday) -> bool {
+    schedule.days.is_empty() || schedule.days.iter().any(|d| to_chrono(*d) == day)
+}
+
+fn bounds(schedule: &Schedule) -> (u16, u16) {
+    let from = schedule.from.map_or(0, |t| t.minutes);
+    let to = schedule.to.map_or(MINUTES_PER_DAY, |t| t.minutes);
+    (from, to)
+}
+
+pub fn is_open(schedule: &Schedule, now: NaiveDateTime) -> bool {
+    let minute = (now.hour() * 60 + now.minute()) as u16;
+    let today = now.weekday();
+    let (from, to) = bounds(schedule);
+
+    if from == to {
+        allows_day(schedule, today)
+    } else if from < to {
+        (from..to).contains(&minute) && allows_day(schedule, today)
+    } else {
+        (minute >= from && allows_day(schedule, today))
+            || (minute < to && allows_day(schedule, today.pred()))
+    }
+}
+
+/// When the window opens next, `now` if it is open already.
+pub fn next_opening(schedule: &Schedule, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    if is_open(schedule, now) {
+        return Some(now);
+    }
+    let (from, _) = bounds(schedule);
+    let opening_time = NaiveTime::from_hms_opt((from / 60).into(), (from % 60).into(), 0)
+        .unwrap_or(NaiveTime::MIN);
+    (0..=7)
+        .filter_map(|offset| now.date().checked_add_days(Days::new(offset)))
+        .map(|date| date.and_time(opening_time))
+        .find(|opening| *opening > now && allows_day(schedule, opening.weekday()))
+}
+
+/// The earliest moment an app could launch if some of its top-level
+/// schedules are closed right now, `None` if none are.
+pub fn blocked_until(conditions: &Conditions, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    conditions
+        .all
+        .iter()
+        .filter_map(|c| match c {
+            Condition::Schedule { schedule } if !is_open(schedule, now) => {
+                next_opening(schedule, now)
+            }
+            _ => None,
+        })
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ClockTime;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        // 2026-10-12 is a Monday.
+        NaiveDate::from_ymd_opt(2026, 10, 12 + day)
+            .and_then(|date| date.and_hms_opt(hour, minute, 0))
+            .unwrap()
+    }
+
+    fn schedule(days: Vec<Weekday>, from: u16, to: u16) -> Schedule {
+        Schedule {
+            days,
+            from: Some(ClockTime { minutes: from }),
+            to: Some(ClockTime { minutes: to }),
+        }
+    }
+
+    #[test]
+    fn daytime_window() {
+        let office = schedule(vec![Weekday::Mon, Weekday::Fri], 9 * 60, 18 * 60);
+        assert!(!is_open(&office, at(0, 8, 59)));
+        assert!(is_open(&office, at(0, 9, 0)));
+        assert!(is_open(&office, at(0, 17, 59)));
+        assert!(!is_open(&office, at(0, 18, 0)));
+        assert!(!is_open(&office, at(1, 12, 0)));
+
+        assert_eq!(next_opening(&office, at(0, 12, 0)), Some(at(0, 12, 0)));
+        assert_eq!(next_opening(&office, at(0, 7, 0)), Some(at(0, 9, 0)));
+        assert_eq!(next_opening(&office, at(0, 19, 0)), Some(at(4, 9, 0)));
+        assert_eq!(next_opening(&office, at(4, 19, 0)), Some(at(7, 9, 0)));
+    }
+
+    #[test]
+    fn window_across_midnight_belongs_to_its_opening_day() {
+        let friday_night = schedule(vec![Weekday::Fri], 22 * 60, 2 * 60);
+        assert!(!is_open(&friday_night, at(4, 21, 59)));
+        assert!(is_open(&friday_night, at(4, 22, 0)));
+        assert!(is_open(&friday_night, at(5, 1, 59)));
+        assert!(!is_open(&friday_night, at(5, 2, 0)));
+        // Friday 01:00 is the tail of Thursday's window, which isn't allowed.
+        assert!(!is_open(&friday_night, at(4, 1, 0)));
+
+        assert_eq!(
+            next_opening(&friday_night, at(5, 3, 0)),
+            Some(at(11, 22, 0))
+        );
+        assert_eq!(next_opening(&friday_night, at(4, 1, 0)), Some(at(4, 22, 0)));
+    }
+
+    #[test]
+    fn whole_days() {
+        let weekend = Schedule {
+            days: vec![Weekday::Sat, Weekday::Sun],
+            from: None,
+            to: None,
+        };
+        assert!(is_open(&weekend, at(5, 0, 0)));
+        assert!(is_open(&weekend, at(6, 23, 59)));
+        assert!(!is_open(&weekend, at(0, 0, 0)));
+        assert_eq!(next_opening(&weekend, at(2, 15, 30)), Some(at(5, 0, 0)));
+    }
+}
@@ -0,0 +1,514 @@
+use crate::app::{ConditionCache, check_stop_conditions};
+use crate::config::{
+    AppConfig, RestartConfig, RestartPolicy, RestartSchedule, ScheduledRestart, Watchdog,
+    WatchdogAction,
+};
+use crate::os::{OsOperations, ProcessUsage};
+use chrono::{DateTime, Local, TimeDelta};
+use std::collections::HashMap;
+use std::process::{Child, ExitStatus};
+use std::time::{Duration, Instant};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// How long the conditions of an app with `stop_when_unmet` have to fail
+/// before it is stopped, unless it sets `stop_after_secs`.
+const DEFAULT_STOP_AFTER: Duration = Duration::from_secs(30);
+/// Time an app gets to quit on SIGTERM before it is killed, unless it sets
+/// `stop_grace_secs`.
+const DEFAULT_STOP_GRACE: Duration = Duration::from_secs(10);
+/// Wait before the first restart, doubled for each further one in the window.
+const RESTART_BACKOFF_BASE: Duration = Duration::from_secs(2);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(300);
+
+/// An app the daemon started and keeps an eye on.
+struct Supervised {
+    child: Child,
+    /// Process group of everything the app started; `setsid` makes it the
+    /// child's PID.
+    pgid: u32,
+    /// When it was asked to quit.
+    stopping_since: Option<Instant>,
+    /// Since when its stop conditions have been failing.
+    unmet_since: Option<Instant>,
+    exit_status: Option<ExitStatus>,
+    started_at: DateTime<Local>,
+    /// Scheduled restarts count from here: the start, or the last skipped
+    /// restart.
+    schedule_from: DateTime<Local>,
+    /// Since when CPU usage has been above the watchdog's limit.
+    cpu_high_since: Option<Instant>,
+    /// The watchdog already acted on the app being over its limits, and
+    /// won't again until it is back under them.
+    over_limits: bool,
+}
+
+/// Restart history of an app, kept across its runs.
+#[derive(Default)]
+struct Restarts {
+    /// Restarts within the app's window, including a scheduled one.
+    recent: Vec<Instant>,
+    not_before: Option<Instant>,
+    /// Exited and not restarted by its policy: it is launched again once its
+    /// conditions have failed and hold again, like at login.
+    waiting_for_unmet: bool,
+    /// Killed by the watchdog, left alone until the next login.
+    given_up: bool,
+}
+
+impl Restarts {
+    fn record_exit(&mut self, config: RestartConfig, status: Option<ExitStatus>, now: Instant) {
+        let failed = status.is_none_or(|status| !status.success());
+        let restart = match config.policy {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => failed,
+            RestartPolicy::Always => true,
+        };
+        if !restart || config.max_restarts == 0 {
+            self.waiting_for_unmet = true;
+            return;
+        }
+        let window = Duration::from_secs(config.window_secs);
+        self.recent.retain(|at| now.duration_since(*at) < window);
+        let backoff = RESTART_BACKOFF_BASE
+            .saturating_mul(1 << self.recent.len().min(16))
+            .min(RESTART_BACKOFF_MAX);
+        let mut at = now + backoff;
+        let max = config.max_restarts as usize;
+        if self.recent.len() >= max {
+            // Out of restarts until enough of them have left the window.
+            at = at.max(self.recent[self.recent.len() - max] + window);
+        }
+        self.recent.push(at);
+        self.not_before = Some(at);
+    }
+
+    fn holds_back(&self, now: Instant) -> bool {
+        self.given_up || self.waiting_for_unmet || self.not_before.is_some_and(|at| at > now)
+    }
+}
+
+/// Remembers what the daemon launched in supervisor mode, so an app that
+/// exits counts as not launched again, subject to its restart policy.
+#[derive(Default)]
+pub struct Supervisor {
+    running: HashMap<String, Supervised>,
+    restarts: HashMap<String, Restarts>,
+    /// Only refreshed for the watchdog; CPU usage is measured between two
+    /// refreshes.
+    system: System,
+}
+
+impl Supervisor {
+    pub fn track(&mut self, name: String, child: Child) {
+        let pgid = child.id();
+        let now = Local::now();
+        self.running.insert(
+            name,
+            Supervised {
+                child,
+                pgid,
+                stopping_since: None,
+                unmet_since: None,
+                exit_status: None,
+                started_at: now,
+                schedule_from: now,
+                cpu_high_since: None,
+                over_limits: false,
+            },
+        );
+    }
+
+    /// Whether anything is running or waiting out a restart backoff.
+    pub fn is_busy(&self) -> bool {
+        let now = Instant::now();
+        !self.running.is_empty()
+            || self
+                .restarts
+                .values()
+                .any(|r| r.not_before.is_some_and(|at| at > now))
+    }
+
+    /// Asks apps with `stop_when_unmet` to quit once their stop conditions have
+    /// failed for a while, and kills apps still around after the grace period.
+    /// A stop, once started, is seen through even if the conditions come back.
+    pub fn stop_unmet(
+        &mut self,
+        os_ops: &dyn OsOperations,
+        cache: &ConditionCache,
+        apps: &[AppConfig],
+    ) {
+        let now = Instant::now();
+        for app in apps {
+            let Some(running) = self.running.get_mut(&app.name) else {
+                continue;
+            };
+            let grace = app
+                .stop_grace_secs
+                .map_or(DEFAULT_STOP_GRACE, Duration::from_secs);
+            let stop_after = app
+                .stop_after_secs
+                .map_or(DEFAULT_STOP_AFTER, Duration::from_secs);
+            match running.stopping_since {
+                None if app.stop_when_unmet => {
+                    let met = check_stop_conditions(os_ops, app, cache).ok;
+                    if unmet_for(&mut running.unmet_since, met, stop_after, now) {
+                        os_ops.stop_app(running.pgid, false);
+                        running.stopping_since = Some(now);
+                    }
+                }
+                Some(since) if since.elapsed() >= grace => os_ops.stop_app(running.pgid, true),
+                _ => {}
+            }
+        }
+    }
+
+    /// Asks apps whose scheduled restart is due to quit. Like any stop, they
+    /// are launched again once their conditions hold.
+    pub fn restart_scheduled(&mut self, os_ops: &dyn OsOperations, apps: &[AppConfig]) {
+        let now = Local::now();
+        for app in apps {
+            let (Some(restart), Some(running)) =
+                (&app.scheduled_restart, self.running.get_mut(&app.name))
+            else {
+                continue;
+            };
+            if running.stopping_since.is_some() || !restart_due(restart, running.schedule_from, now)
+            {
+                continue;
+            }
+            let started_recently = restart
+                .skip_if_started_within_mins
+                .is_some_and(|mins| now - running.started_at < minutes(mins));
+            if started_recently {
+                running.schedule_from = now;
+            } else {
+                os_ops.stop_app(running.pgid, false);
+                running.stopping_since = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Checks apps with a watchdog against their limits and notifies about,
+    /// restarts or kills the ones over them. Killed apps aren't launched
+    /// again until the next login.
+    pub fn watch(&mut self, os_ops: &dyn OsOperations, apps: &[AppConfig]) {
+        let watched = apps
+            .iter()
+            .any(|app| app.watchdog.is_some() && self.running.contains_key(&app.name));
+        if !watched {
+            return;
+        }
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().with_memory().with_cpu(),
+        );
+        let now = Instant::now();
+        for app in apps {
+            let (Some(watchdog), Some(running)) = (app.watchdog, self.running.get_mut(&app.name))
+            else {
+                continue;
+            };
+            if running.stopping_since.is_some() {
+                continue;
+            }
+            let usage = os_ops.process_group_usage(running.pgid, &self.system);
+            let Some(problem) = over_limits(watchdog, usage, &mut running.cpu_high_since, now)
+            else {
+                running.over_limits = false;
+                continue;
+            };
+            if running.over_limits {
+                continue;
+            }
+            running.over_limits = true;
+            let action = match watchdog.action {
+                WatchdogAction::Notify => "",
+                WatchdogAction::Restart => " Restarting it.",
+                WatchdogAction::Kill => " Killing it.",
+            };
+            os_ops.notify(&app.name, &format!("{problem}.{action}"));
+            match watchdog.action {
+                WatchdogAction::Notify => {}
+                WatchdogAction::Restart => {
+                    os_ops.stop_app(running.pgid, false);
+                    running.stopping_since = Some(now);
+                }
+                WatchdogAction::Kill => {
+                    os_ops.stop_app(running.pgid, true);
+                    running.stopping_since = Some(now);
+                    self.restarts.entry(app.name.clone()).or_default().given_up = true;
+                }
+            }
+        }
+    }
+
+    /// Forgets apps with nothing left running and clears their `launched`
+    /// flag once their restart policy allows, so a later round can start them
+    /// again. Apps the supervisor stopped itself don't count as restarts and
+    /// may come back as soon as their conditions hold.
+    pub fn reap(
+        &mut self,
+        os_ops: &dyn OsOperations,
+        cache: &ConditionCache,
+        apps: &mut [AppConfig],
+    ) {
+        let now = Instant::now();
+        for app in apps.iter_mut() {
+            if let Some(restarts) = self.restarts.get_mut(&app.name)
+                && restarts.waiting_for_unmet
+                && !check_stop_conditions(os_ops, app, cache).ok
+            {
+                restarts.waiting_for_unmet = false;
+            }
+            if let Some(running) = self.running.get_mut(&app.name) {
+                // Collect the exit status, or the shell lingers as a zombie
+                // and keeps the group alive.
+                if running.exit_status.is_none() {
+                    running.exit_status = running.child.try_wait().ok().flatten();
+                }
+                if !os_ops.is_process_group_alive(running.pgid)
+                    && let Some(mut exited) = self.running.remove(&app.name)
+                    && exited.stopping_since.is_none()
+                {
+                    let status = exited
+                        .exit_status
+                        .or_else(|| exited.child.try_wait().ok().flatten());
+                    self.restarts
+                        .entry(app.name.clone())
+                        .or_default()
+                        .record_exit(app.restart, status, now);
+                }
+            }
+            app.launched = self.running.contains_key(&app.name)
+                || self
+                    .restarts
+                    .get(&app.name)
+                    .is_some_and(|r| r.holds_back(now));
+        }
+    }
+}
+
+/// Whether a restart scheduled after `from` has come up by `now`.
+fn restart_due(restart: &ScheduledRestart, from: DateTime<Local>, now: DateTime<Local>) -> bool {
+    match &restart.when {
+        RestartSchedule::Cron(cron) => cron.next_after(&from).is_some_and(|at| at <= now),
+        RestartSchedule::EveryHours(every) => now - from >= hours(every.get()),
+    }
+}
+
+/// Whether the conditions have been failing for at least `after`. Keeps track
+/// of since when; holding again starts the count over.
+fn unmet_for(unmet_since: &mut Option<Instant>, met: bool, after: Duration, now: Instant) -> bool {
+    if met {
+        *unmet_since = None;
+        return false;
+    }
+    now.duration_since(*unmet_since.get_or_insert(now)) >= after
+}
+
+/// What is over the watchdog's limits, if anything. Keeps track of how long
+/// CPU usage has been high; the first sample after a start always reads low.
+fn over_limits(
+    watchdog: Watchdog,
+    usage: ProcessUsage,
+    cpu_high_since: &mut Option<Instant>,
+    now: Instant,
+) -> Option<String> {
+    if watchdog
+        .max_cpu_percent
+        .is_some_and(|max| usage.cpu_percent > max)
+    {
+        cpu_high_since.get_or_insert(now);
+    } else {
+        *cpu_high_since = None;
+    }
+    let memory_mb = usage.memory / 1_000_000;
+    if let Some(max) = watchdog.max_memory_mb
+        && memory_mb > max
+    {
+        return Some(format!(
+            "Uses {memory_mb} MB of memory, over its {max} MB limit"
+        ));
+    }
+    let cpu_for = Duration::from_secs(watchdog.cpu_minutes.saturating_mul(60));
+    if let (Some(max), Some(since)) = (watchdog.max_cpu_percent, *cpu_high_since)
+        && now.duration_since(since) >= cpu_for
+    {
+        return Some(format!(
+            "Has used over {max}% CPU for {} minutes",
+            watchdog.cpu_minutes
+        ));
+    }
+    None
+}
+
+/// Saturating, so a silly value in the config can't panic.
+fn minutes(minutes: u64) -> TimeDelta {
+    i64::try_from(minutes)
+        .ok()
+        .and_then(TimeDelta::try_minutes)
+        .unwrap_or(TimeDelta::MAX)
+}
+
+fn hours(hours: u64) -> TimeDelta {
+    i64::try_from(hours)
+        .ok()
+        .and_then(TimeDelta::try_hours)
+        .unwrap_or(TimeDelta::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheduled_restarts_come_due() {
+        use chrono::TimeZone;
+        let at = |hour| Local.with_ymd_and_hms(2026, 10, 17, hour, 30, 0).unwrap();
+        let restart = |toml_str| toml::from_str::<ScheduledRestart>(toml_str).unwrap();
+
+        let nightly = restart("cron = \"0 4 * * *\"");
+        assert!(!restart_due(&nightly, at(3), at(3)));
+        assert!(restart_due(&nightly, at(3), at(4)));
+        assert!(!restart_due(&nightly, at(4), at(23)));
+
+        let every = restart("every_hours = 6");
+        assert!(!restart_due(&every, at(0), at(5)));
+        assert!(restart_due(&every, at(0), at(6)));
+    }
+
+    fn exited(code: i32) -> Option<ExitStatus> {
+        use std::os::unix::process::ExitStatusExt;
+        Some(ExitStatus::from_raw(code << 8))
+    }
+
+    fn policy(policy: RestartPolicy) -> RestartConfig {
+        RestartConfig {
+            policy,
+            ..RestartConfig::default()
+        }
+    }
+
+    #[test]
+    fn restarts_back_off_and_run_out_within_the_window() {
+        let config = RestartConfig {
+            policy: RestartPolicy::OnFailure,
+            max_restarts: 3,
+            window_secs: 600,
+        };
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let mut restarts = Restarts::default();
+
+        restarts.record_exit(config, exited(1), start);
+        assert_eq!(restarts.not_before, Some(secs(2)));
+        restarts.record_exit(config, exited(1), secs(10));
+        assert_eq!(restarts.not_before, Some(secs(14)));
+        restarts.record_exit(config, exited(1), secs(20));
+        assert_eq!(restarts.not_before, Some(secs(28)));
+
+        // Out of restarts: held back until the first one leaves the window,
+        // not for good.
+        restarts.record_exit(config, exited(1), secs(30));
+        assert_eq!(restarts.not_before, Some(secs(602)));
+        assert!(restarts.holds_back(secs(601)));
+        assert!(!restarts.holds_back(secs(602)));
+        assert!(!restarts.given_up && !restarts.waiting_for_unmet);
+
+        // Crashes long after that start the backoff over.
+        let mut restarts = Restarts::default();
+        restarts.record_exit(config, exited(1), start);
+        restarts.record_exit(config, None, secs(700));
+        assert_eq!(restarts.not_before, Some(secs(702)));
+    }
+
+    #[test]
+    fn restart_policy_decides_which_exits_restart() {
+        use RestartPolicy::*;
+        let now = Instant::now();
+        for (policy_, status, restarted) in [
+            (Always, exited(0), true),
+            (Always, exited(1), true),
+            (OnFailure, exited(0), false),
+            (OnFailure, exited(1), true),
+            (OnFailure, None, true),
+            (Never, exited(0), false),
+            (Never, exited(1), false),
+        ] {
+            let mut restarts = Restarts::default();
+            restarts.record_exit(policy(policy_), status, now);
+            assert_eq!(
+                restarts.not_before.is_some(),
+                restarted,
+                "{policy_:?} {status:?}"
+            );
+            // Not restarting only waits for the conditions to come back, and
+            // uses up none of the budget.
+            assert_eq!(restarts.waiting_for_unmet, !restarted);
+            assert_eq!(restarts.recent.len(), usize::from(restarted));
+            assert!(!restarts.given_up);
+        }
+    }
+
+    #[test]
+    fn stops_only_after_conditions_keep_failing() {
+        let after = Duration::from_secs(30);
+        let start = Instant::now();
+        let secs = |n| start + Duration::from_secs(n);
+        let mut since = None;
+
+        assert!(!unmet_for(&mut since, false, after, start));
+        assert!(!unmet_for(&mut since, false, after, secs(25)));
+        // One round holding again starts the count over.
+        assert!(!unmet_for(&mut since, true, after, secs(28)));
+        assert!(!unmet_for(&mut since, false, after, secs(33)));
+        assert!(!unmet_for(&mut since, false, after, secs(62)));
+        assert!(unmet_for(&mut since, false, after, secs(63)));
+
+        let mut since = None;
+        assert!(unmet_for(&mut since, false, Duration::ZERO, start));
+    }
+
+    #[test]
+    fn watchdog_limits() {
+        let watchdog = Watchdog {
+            max_memory_mb: Some(2000),
+            max_cpu_percent: Some(150.0),
+            cpu_minutes: 5,
+            action: WatchdogAction::Notify,
+        };
+        let usage = |memory, cpu_percent| ProcessUsage {
+            memory,
+            cpu_percent,
+        };
+        let start = Instant::now();
+        let mut since = None;
+
+        // Megabytes, not mebibytes: 2000 MiB is well over 2000 MB.
+        assert_eq!(
+            over_limits(watchdog, usage(2_000_000_000, 0.0), &mut since, start),
+            None
+        );
+        assert!(over_limits(watchdog, usage(2_001_000_000, 0.0), &mut since, start).is_some());
+
+        assert_eq!(
+            over_limits(watchdog, usage(0, 200.0), &mut since, start),
+            None
+        );
+        let later = start + Duration::from_secs(4 * 60);
+        assert_eq!(
+            over_limits(watchdog, usage(0, 200.0), &mut since, later),
+            None
+        );
+        let later = start + Duration::from_secs(5 * 60);
+        assert!(over_limits(watchdog, usage(0, 200.0), &mut since, later).is_some());
+
+        // Dropping below the limit starts the count over.
+        assert_eq!(
+            over_limits(watchdog, usage(0, 100.0), &mut since, later),
+            None
+        );
+        assert_eq!(since, None);
+    }
+}